@@ -1,15 +1,122 @@
+use bzip2::bufread::MultiBzDecoder;
 use clap::{App, Arg};
+use encoding_rs::Encoding;
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use flate2::bufread::MultiGzDecoder;
+use regex::bytes::Regex;
 use std::error::Error;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, ErrorKind, Read, Write};
+use xz2::bufread::XzDecoder;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
 #[derive(Debug)]
 pub struct Config {
     files: Vec<String>,
-    number_lines: bool,
-    number_nonblank_lines: bool,
+    numbering: Option<Numbering>,
+    show_ends: bool,
+    show_tabs: bool,
+    show_nonprinting: bool,
+    squeeze_blank: bool,
+    decompress: bool,
+    from_encoding: Option<&'static Encoding>,
+    bom: Option<BomMode>,
+    line_ending: Option<LineEnding>,
+}
+
+#[derive(Debug)]
+enum NumberStyle {
+    All,
+    NonBlank,
+    Matching(Regex),
+}
+
+#[derive(Debug, PartialEq)]
+enum NumberFormat {
+    Left,
+    Right,
+    RightZero,
+}
+
+#[derive(Debug)]
+struct Numbering {
+    style: NumberStyle,
+    format: NumberFormat,
+    start: i64,
+    increment: i64,
+    width: usize,
+    separator: String,
+    renumber: bool,
+    // Indent unnumbered lines to line up with numbered ones, as nl does. The
+    // cat flags -n and -b leave them as they are.
+    pad_unselected: bool,
+}
+
+fn parse_number_style(val: &str) -> MyResult<Option<NumberStyle>> {
+    match val {
+        "a" => Ok(Some(NumberStyle::All)),
+        "t" => Ok(Some(NumberStyle::NonBlank)),
+        "n" => Ok(None),
+        _ => match val.strip_prefix('p') {
+            Some(pattern) => Regex::new(pattern)
+                .map(|re| Some(NumberStyle::Matching(re)))
+                .map_err(|_| From::from(val)),
+            None => Err(From::from(val)),
+        },
+    }
+}
+
+fn parse_number_format(val: &str) -> MyResult<NumberFormat> {
+    match val {
+        "ln" => Ok(NumberFormat::Left),
+        "rn" => Ok(NumberFormat::Right),
+        "rz" => Ok(NumberFormat::RightZero),
+        _ => Err(From::from(val)),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum BomMode {
+    Strip,
+    Add,
+}
+
+#[derive(Debug, PartialEq)]
+enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+fn parse_bom_mode(val: &str) -> MyResult<BomMode> {
+    match val {
+        "strip" => Ok(BomMode::Strip),
+        "add" => Ok(BomMode::Add),
+        _ => Err(From::from(val)),
+    }
+}
+
+fn parse_line_ending(val: &str) -> MyResult<LineEnding> {
+    match val {
+        "lf" => Ok(LineEnding::Lf),
+        "crlf" => Ok(LineEnding::Crlf),
+        _ => Err(From::from(val)),
+    }
+}
+
+fn parse_encoding(val: &str) -> MyResult<&'static Encoding> {
+    Encoding::for_label(val.as_bytes()).ok_or_else(|| From::from(val))
+}
+
+fn parse_int(val: &str) -> MyResult<i64> {
+    val.parse().map_err(|_| From::from(val))
+}
+
+fn parse_positive_int(val: &str) -> MyResult<usize> {
+    match val.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(From::from(val)),
+    }
 }
 
 pub fn get_args() -> MyResult<Config> {
@@ -39,49 +146,591 @@ pub fn get_args() -> MyResult<Config> {
                 .help("Number nonblank lines")
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("body_numbering")
+                .long("body-numbering")
+                .value_name("STYLE")
+                .help("Number lines like nl -b: a (all), t (nonblank), n (none), pREGEX")
+                .conflicts_with_all(&["number", "number_nonblank"]),
+        )
+        .arg(
+            Arg::with_name("start_number")
+                .long("starting-line-number")
+                .value_name("NUMBER")
+                .allow_hyphen_values(true)
+                .default_value("1")
+                .help("First line number"),
+        )
+        .arg(
+            Arg::with_name("increment")
+                .long("line-increment")
+                .value_name("NUMBER")
+                .allow_hyphen_values(true)
+                .default_value("1")
+                .help("Line number increment"),
+        )
+        .arg(
+            Arg::with_name("number_width")
+                .long("number-width")
+                .value_name("WIDTH")
+                .default_value("6")
+                .help("Width of line numbers"),
+        )
+        .arg(
+            Arg::with_name("number_separator")
+                .long("number-separator")
+                .value_name("STRING")
+                .default_value("\t")
+                .hide_default_value(true)
+                .help("Text between line number and line [default: TAB]"),
+        )
+        .arg(
+            Arg::with_name("number_format")
+                .long("number-format")
+                .value_name("FORMAT")
+                .default_value("rn")
+                .help("Left, right or zero-padded right justification"),
+        )
+        .arg(
+            Arg::with_name("no_renumber")
+                .long("no-renumber")
+                .help("Continue line numbers across files, as is always done for the rotated parts of one log")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("show_all")
+                .short("A")
+                .long("show-all")
+                .help("Equivalent to -vET")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("show_ends")
+                .short("E")
+                .long("show-ends")
+                .help("Display $ at end of each line")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("show_tabs")
+                .short("T")
+                .long("show-tabs")
+                .help("Display TAB characters as ^I")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("show_nonprinting")
+                .short("v")
+                .long("show-nonprinting")
+                .help("Use ^ and M- notation, except for LFD and TAB")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("squeeze_blank")
+                .short("s")
+                .long("squeeze-blank")
+                .help("Suppress repeated empty output lines")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("decompress")
+                .short("z")
+                .long("decompress")
+                .help("Decompress gzip, bzip2, xz and zstd input, as is always done when numbering lines")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("from_encoding")
+                .long("from-encoding")
+                .value_name("ENCODING")
+                .help("Transcode input to UTF-8, e.g. utf-16le, utf-16be, latin1, windows-1252"),
+        )
+        .arg(
+            Arg::with_name("bom")
+                .long("bom")
+                .value_name("MODE")
+                .help("Strip or add a UTF-8 byte order mark: strip, add"),
+        )
+        .arg(
+            Arg::with_name("line_endings")
+                .long("line-endings")
+                .value_name("EOL")
+                .help("Convert CRLF, CR and LF line endings: lf, crlf"),
+        )
         .get_matches();
 
+    let style = if matches.is_present("number") {
+        Some(NumberStyle::All)
+    } else if matches.is_present("number_nonblank") {
+        Some(NumberStyle::NonBlank)
+    } else {
+        matches
+            .value_of("body_numbering")
+            .map(parse_number_style)
+            .transpose()
+            .map_err(|e| format!("invalid body numbering style -- {}", e))?
+            .flatten()
+    };
+
+    let start = matches
+        .value_of("start_number")
+        .map(parse_int)
+        .transpose()
+        .map_err(|e| format!("invalid starting line number -- {}", e))?;
+    let increment = matches
+        .value_of("increment")
+        .map(parse_int)
+        .transpose()
+        .map_err(|e| format!("invalid line number increment -- {}", e))?;
+    let width = matches
+        .value_of("number_width")
+        .map(parse_positive_int)
+        .transpose()
+        .map_err(|e| format!("invalid line number field width -- {}", e))?;
+    let format = matches
+        .value_of("number_format")
+        .map(parse_number_format)
+        .transpose()
+        .map_err(|e| format!("invalid line numbering format -- {}", e))?;
+
+    let numbering = style.map(|style| Numbering {
+        style,
+        format: format.unwrap(),
+        start: start.unwrap(),
+        increment: increment.unwrap(),
+        width: width.unwrap(),
+        separator: matches.value_of("number_separator").unwrap().to_string(),
+        renumber: !matches.is_present("no_renumber"),
+        pad_unselected: !matches.is_present("number") && !matches.is_present("number_nonblank"),
+    });
+
+    let from_encoding = matches
+        .value_of("from_encoding")
+        .map(parse_encoding)
+        .transpose()
+        .map_err(|e| format!("unknown encoding -- {}", e))?;
+    let bom = matches
+        .value_of("bom")
+        .map(parse_bom_mode)
+        .transpose()
+        .map_err(|e| format!("invalid BOM mode -- {}", e))?;
+    let line_ending = matches
+        .value_of("line_endings")
+        .map(parse_line_ending)
+        .transpose()
+        .map_err(|e| format!("invalid line ending -- {}", e))?;
+
+    let show_all = matches.is_present("show_all");
+
     Ok(Config {
         files: matches.values_of_lossy("files").unwrap(),
-        number_lines: matches.is_present("number"),
-        number_nonblank_lines: matches.is_present("number_nonblank"),
+        numbering,
+        show_ends: show_all || matches.is_present("show_ends"),
+        show_tabs: show_all || matches.is_present("show_tabs"),
+        show_nonprinting: show_all || matches.is_present("show_nonprinting"),
+        squeeze_blank: matches.is_present("squeeze_blank"),
+        decompress: matches.is_present("decompress"),
+        from_encoding,
+        bom,
+        line_ending,
     })
 }
 
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+impl Numbering {
+    fn selects(&self, line: &[u8]) -> bool {
+        match &self.style {
+            NumberStyle::All => true,
+            NumberStyle::NonBlank => line != b"\n",
+            NumberStyle::Matching(re) => re.is_match(line.strip_suffix(b"\n").unwrap_or(line)),
+        }
+    }
+
+    fn write_prefix(&self, out: &mut impl Write, num: i64) -> io::Result<()> {
+        let width = self.width;
+        match self.format {
+            NumberFormat::Left => write!(out, "{:<width$}", num)?,
+            NumberFormat::Right => write!(out, "{:>width$}", num)?,
+            NumberFormat::RightZero => write!(out, "{:0width$}", num)?,
+        }
+        out.write_all(self.separator.as_bytes())
+    }
+
+    // Like nl, counts the separator in bytes
+    fn write_padding(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "{:1$}", "", self.width + self.separator.len())
+    }
+}
+
+impl Config {
+    fn transforms_lines(&self) -> bool {
+        self.numbering.is_some()
+            || self.show_ends
+            || self.show_tabs
+            || self.show_nonprinting
+            || self.squeeze_blank
+            || self.line_ending == Some(LineEnding::Crlf)
     }
+
+    fn rewrites_input(&self) -> bool {
+        self.decompress
+            || self.from_encoding.is_some()
+            || self.bom.is_some()
+            || self.line_ending.is_some()
+    }
+}
+
+// Keeps the concrete stream type around so that `io::copy` can pick the
+// zero-copy path for it, which a `Box<dyn Read>` would hide.
+enum Input {
+    Stdin(io::Stdin),
+    File(File),
+    Decoded(Box<dyn Read>),
 }
 
-fn print_file(file: Box<dyn BufRead>, config: &Config) -> MyResult<()> {
-    let mut blank_count = 0;
-    for (line_num, line) in file.lines().enumerate() {
-        let line = line?;
-        if line.is_empty() {
-            blank_count += 1;
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::Stdin(stdin) => stdin.read(buf),
+            Input::File(file) => file.read(buf),
+            Input::Decoded(reader) => reader.read(buf),
         }
+    }
+}
 
-        let prefix = if config.number_lines {
-            format!("{:>6}\t", line_num + 1)
-        } else if config.number_nonblank_lines && !line.is_empty() {
-            format!("{:>6}\t", line_num + 1 - blank_count)
+#[derive(Debug, PartialEq)]
+enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    fn detect(magic: &[u8]) -> Option<Compression> {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if magic.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
         } else {
-            String::from("")
-        };
+            None
+        }
+    }
+}
+
+// Reads up to `len` bytes, fewer only at end of input.
+fn read_prefix(file: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut prefix = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut prefix)?;
+    Ok(prefix)
+}
+
+// Sniffs the magic bytes and wraps the stream in the matching decoder.
+// Input that is not compressed is passed through unchanged, like `zcat -f`.
+fn decoder(mut file: impl Read + 'static) -> MyResult<Box<dyn Read>> {
+    let magic = read_prefix(&mut file, 6)?;
+    let compression = Compression::detect(&magic);
+    let reader = BufReader::new(Cursor::new(magic).chain(file));
+    Ok(match compression {
+        Some(Compression::Gzip) => Box::new(MultiGzDecoder::new(reader)),
+        Some(Compression::Bzip2) => Box::new(MultiBzDecoder::new(reader)),
+        Some(Compression::Xz) => Box::new(XzDecoder::new_multi_decoder(reader)),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::with_buffer(reader)?),
+        None => Box::new(reader),
+    })
+}
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+fn fix_bom(mut file: impl Read + 'static, mode: &BomMode) -> MyResult<Box<dyn Read>> {
+    let prefix = read_prefix(&mut file, UTF8_BOM.len())?;
+    let rest = prefix.strip_prefix(UTF8_BOM).unwrap_or(&prefix);
+    let head = match mode {
+        BomMode::Strip => rest.to_vec(),
+        BomMode::Add => [UTF8_BOM, rest].concat(),
+    };
+    Ok(Box::new(Cursor::new(head).chain(file)))
+}
+
+// Rewrites CRLF and lone CR line endings as LF.
+struct LfLineEndings<R> {
+    inner: R,
+    after_cr: bool,
+}
+
+impl<R: Read> Read for LfLineEndings<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+
+            let mut len = 0;
+            for i in 0..n {
+                let byte = buf[i];
+                if byte == b'\n' && self.after_cr {
+                    self.after_cr = false;
+                    continue;
+                }
+                self.after_cr = byte == b'\r';
+                buf[len] = if self.after_cr { b'\n' } else { byte };
+                len += 1;
+            }
+
+            // A chunk holding only the LF of a split CRLF leaves nothing to
+            // return, and Ok(0) would be taken for end of input.
+            if len > 0 {
+                return Ok(len);
+            }
+        }
+    }
+}
+
+fn open(filename: &str, config: &Config) -> MyResult<Input> {
+    let input = match filename {
+        "-" => Input::Stdin(io::stdin()),
+        _ => Input::File(File::open(filename)?),
+    };
+    // Numbering the lines of compressed data would be no use to anyone
+    let decompress = config.decompress || config.numbering.is_some();
+    if !config.rewrites_input() && !decompress {
+        return Ok(input);
+    }
+
+    let mut reader: Box<dyn Read> = Box::new(input);
+    if decompress {
+        reader = decoder(reader)?;
+    }
+    if let Some(encoding) = config.from_encoding {
+        reader = Box::new(
+            DecodeReaderBytesBuilder::new()
+                .encoding(Some(encoding))
+                .build(reader),
+        );
+    }
+    if let Some(mode) = &config.bom {
+        reader = fix_bom(reader, mode)?;
+    }
+    if config.line_ending.is_some() {
+        reader = Box::new(LfLineEndings {
+            inner: reader,
+            after_cr: false,
+        });
+    }
+    Ok(Input::Decoded(reader))
+}
+
+// On Linux `io::copy` uses copy_file_range, sendfile or splice when both
+// ends are files or pipes, and falls back to a plain read/write loop.
+fn copy_raw(mut file: impl Read) -> MyResult<()> {
+    io::copy(&mut file, &mut io::stdout().lock())?;
+    Ok(())
+}
+
+fn copy_file(input: Input) -> MyResult<()> {
+    match input {
+        Input::Stdin(stdin) => copy_raw(stdin.lock()),
+        Input::File(file) => copy_raw(file),
+        Input::Decoded(reader) => copy_raw(reader),
+    }
+}
 
-        println!("{}{}", prefix, line);
+fn push_caret(buf: &mut Vec<u8>, byte: u8) {
+    match byte {
+        0..=31 => buf.extend_from_slice(&[b'^', byte + 64]),
+        127 => buf.extend_from_slice(b"^?"),
+        _ => buf.push(byte),
     }
+}
+
+fn render_line(line: &[u8], config: &Config, buf: &mut Vec<u8>) {
+    let (mut body, newline) = match line.strip_suffix(b"\n") {
+        Some(body) => (body, true),
+        None => (line, false),
+    };
+
+    // Like GNU cat, -E on its own still makes a DOS line ending visible.
+    let mut carriage_return = false;
+    if config.show_ends && newline {
+        if let Some(stripped) = body.strip_suffix(b"\r") {
+            body = stripped;
+            carriage_return = true;
+        }
+    }
+
+    for &byte in body {
+        match byte {
+            b'\t' if config.show_tabs => buf.extend_from_slice(b"^I"),
+            b'\t' => buf.push(byte),
+            128..=255 if config.show_nonprinting => {
+                buf.extend_from_slice(b"M-");
+                push_caret(buf, byte - 128);
+            }
+            _ if config.show_nonprinting => push_caret(buf, byte),
+            _ => buf.push(byte),
+        }
+    }
+
+    if carriage_return {
+        buf.extend_from_slice(b"^M");
+    }
+    if newline {
+        if config.show_ends {
+            buf.push(b'$');
+        }
+        if config.line_ending == Some(LineEnding::Crlf) {
+            buf.push(b'\r');
+        }
+        buf.push(b'\n');
+    }
+}
+
+fn print_file(mut file: impl BufRead, config: &Config, line_num: &mut i64) -> MyResult<()> {
+    let mut out = BufWriter::new(io::stdout());
+    let mut line = Vec::new();
+    let mut rendered = Vec::new();
+    let mut prev_blank = false;
+
+    loop {
+        line.clear();
+        if file.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+
+        let blank = line == b"\n";
+        if config.squeeze_blank && blank && prev_blank {
+            continue;
+        }
+        prev_blank = blank;
+
+        if let Some(numbering) = &config.numbering {
+            if numbering.selects(&line) {
+                numbering.write_prefix(&mut out, *line_num)?;
+                *line_num = line_num
+                    .checked_add(numbering.increment)
+                    .ok_or("line number overflow")?;
+            } else if numbering.pad_unselected {
+                numbering.write_padding(&mut out)?;
+            }
+        }
+
+        rendered.clear();
+        render_line(&line, config, &mut rendered);
+        out.write_all(&rendered)?;
+    }
+    out.flush()?;
     Ok(())
 }
 
+// The name of the log that `filename` is part of once rotated, so that
+// app.log.2.gz, app.log.1 and app.log all give app.log
+fn log_name(filename: &str) -> &str {
+    let name = [".gz", ".bz2", ".xz", ".zst"]
+        .iter()
+        .find_map(|ext| filename.strip_suffix(ext))
+        .unwrap_or(filename);
+    match name.rsplit_once('.') {
+        Some((base, num)) if !num.is_empty() && num.bytes().all(|b| b.is_ascii_digit()) => base,
+        _ => name,
+    }
+}
+
 pub fn run(config: Config) -> MyResult<()> {
+    let start = config.numbering.as_ref().map_or(1, |n| n.start);
+    let mut line_num = start;
+    let mut prev_filename: Option<&str> = None;
     for filename in &config.files {
-        match open(&filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
-            Ok(file) => print_file(file, &config)?,
+        // The rotated parts of a log are numbered as the one log they were
+        let same_log = prev_filename
+            .is_some_and(|prev| prev != filename && log_name(prev) == log_name(filename));
+        if config.numbering.as_ref().is_none_or(|n| n.renumber) && !same_log {
+            line_num = start;
+        }
+        prev_filename = Some(filename);
+
+        let result = open(filename, &config).and_then(|input| {
+            if config.transforms_lines() {
+                print_file(BufReader::new(input), &config, &mut line_num)
+            } else {
+                copy_file(input)
+            }
+        });
+
+        // A corrupt or truncated archive only spoils its own file, but
+        // there is no point carrying on once stdout has gone away.
+        if let Err(err) = result {
+            match err.downcast_ref::<io::Error>() {
+                Some(e) if e.kind() == ErrorKind::BrokenPipe => return Err(err),
+                _ => eprintln!("{}: {}", filename, err),
+            }
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        log_name, parse_number_format, parse_number_style, LfLineEndings, NumberFormat, NumberStyle,
+    };
+    use std::io::{Cursor, Read};
+
+    #[test]
+    fn test_parse_number_style() {
+        assert!(matches!(
+            parse_number_style("a"),
+            Ok(Some(NumberStyle::All))
+        ));
+        assert!(matches!(
+            parse_number_style("t"),
+            Ok(Some(NumberStyle::NonBlank))
+        ));
+        assert!(matches!(parse_number_style("n"), Ok(None)));
+
+        // A "p" prefix selects lines matching the rest of the value
+        let res = parse_number_style("p^fn ");
+        assert!(matches!(&res, Ok(Some(NumberStyle::Matching(re))) if re.as_str() == "^fn "));
+
+        // Unknown styles and bad patterns are errors
+        let res = parse_number_style("x");
+        assert_eq!(res.unwrap_err().to_string(), "x");
+        let res = parse_number_style("p(");
+        assert_eq!(res.unwrap_err().to_string(), "p(");
+    }
+
+    #[test]
+    fn test_parse_number_format() {
+        assert_eq!(parse_number_format("ln").unwrap(), NumberFormat::Left);
+        assert_eq!(parse_number_format("rn").unwrap(), NumberFormat::Right);
+        assert_eq!(parse_number_format("rz").unwrap(), NumberFormat::RightZero);
+        assert_eq!(parse_number_format("lz").unwrap_err().to_string(), "lz");
+    }
+
+    #[test]
+    fn test_log_name() {
+        assert_eq!(log_name("app.log"), "app.log");
+        assert_eq!(log_name("app.log.1"), "app.log");
+        assert_eq!(log_name("app.log.3.gz"), "app.log");
+        assert_eq!(log_name("dir/app.log.12.zst"), "dir/app.log");
+        assert_eq!(log_name("fox.txt.gz"), "fox.txt");
+        assert_eq!(log_name("v1.2"), "v1");
+        assert_eq!(log_name("notes.txt."), "notes.txt.");
+    }
+
+    #[test]
+    fn test_lf_line_endings() {
+        // A CRLF split across two reads still becomes a single LF
+        let inner = Cursor::new("one\r").chain(Cursor::new("\ntwo\rthree\r\r\n"));
+        let mut reader = LfLineEndings {
+            inner,
+            after_cr: false,
+        };
+        let mut out = String::new();
+        assert!(reader.read_to_string(&mut out).is_ok());
+        assert_eq!(out, "one\ntwo\nthree\n\n");
+    }
+}
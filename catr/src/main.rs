@@ -1,6 +1,6 @@
 fn main() {
     if let Err(e) = catr::get_args().and_then(catr::run) {
-        eprint!("{}", e);
+        eprintln!("{}", e);
         std::process::exit(1);
     }
 }
@@ -11,6 +11,8 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const VISUAL: &str = "tests/inputs/visual.txt";
+const BINARY: &str = "tests/inputs/binary.bin";
 
 // --------------------------------------------------
 #[test]
@@ -54,7 +56,7 @@ fn skips_bad_file() -> TestResult {
 
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    let expected = fs::read_to_string(expected_file)?;
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
@@ -198,3 +200,370 @@ fn all_n() -> TestResult {
 fn all_b() -> TestResult {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn visual_show_all() -> TestResult {
+    run(&["-A", VISUAL], "tests/expected/visual.txt.A.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn visual_show_ends() -> TestResult {
+    run(&["-E", VISUAL], "tests/expected/visual.txt.E.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn visual_show_tabs() -> TestResult {
+    run(&["--show-tabs", VISUAL], "tests/expected/visual.txt.T.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn visual_show_nonprinting() -> TestResult {
+    run(&["-v", VISUAL], "tests/expected/visual.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn visual_squeeze() -> TestResult {
+    run(&["-s", VISUAL], "tests/expected/visual.txt.s.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn visual_squeeze_n() -> TestResult {
+    run(&["-s", "-n", VISUAL], "tests/expected/visual.txt.sn.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn visual_squeeze_b() -> TestResult {
+    run(&["-sb", VISUAL], "tests/expected/visual.txt.sb.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn visual_show_all_n() -> TestResult {
+    run(&["-A", "-n", VISUAL], "tests/expected/visual.txt.An.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary() -> TestResult {
+    run(&[BINARY], BINARY)
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_stdin() -> TestResult {
+    let input = fs::read(BINARY)?;
+    Command::cargo_bin(PRG)?
+        .arg("-")
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(input);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_concatenated() -> TestResult {
+    let mut expected = fs::read(BINARY)?;
+    expected.extend(fs::read(FOX)?);
+    expected.extend(fs::read(BINARY)?);
+    Command::cargo_bin(PRG)?
+        .args([BINARY, FOX, BINARY])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn gzip_z() -> TestResult {
+    run(
+        &["-z", "tests/inputs/fox.txt.gz"],
+        "tests/expected/fox.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn gzip_not_decompressed_without_z() -> TestResult {
+    run(&["tests/inputs/fox.txt.gz"], "tests/inputs/fox.txt.gz")
+}
+
+// --------------------------------------------------
+#[test]
+fn rotated_logs_n() -> TestResult {
+    // Numbering decompresses without -z and runs on across the parts
+    run(
+        &[
+            "-n",
+            "tests/inputs/app.log.3.gz",
+            "tests/inputs/app.log.2.gz",
+            "tests/inputs/app.log",
+        ],
+        "tests/expected/app.log.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bzip2_z_n() -> TestResult {
+    run(
+        &["-z", "-n", "tests/inputs/spiders.txt.bz2"],
+        "tests/expected/spiders.txt.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn xz_decompress_b() -> TestResult {
+    run(
+        &["--decompress", "-b", "tests/inputs/the-bustle.txt.xz"],
+        "tests/expected/the-bustle.txt.b.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn zstd_z() -> TestResult {
+    run(
+        &["-z", "tests/inputs/the-bustle.txt.zst"],
+        "tests/expected/the-bustle.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn zstd_z_stdin() -> TestResult {
+    let input = fs::read("tests/inputs/the-bustle.txt.zst")?;
+    let expected = fs::read_to_string("tests/expected/the-bustle.txt.n.stdin.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["-z", "-n"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn mixed_z() -> TestResult {
+    run(
+        &[
+            "-z",
+            "tests/inputs/fox.txt.gz",
+            "tests/inputs/spiders.txt.bz2",
+            BUSTLE,
+        ],
+        "tests/expected/all.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn gzip_members_z() -> TestResult {
+    let expected = fs::read_to_string(FOX)? + &fs::read_to_string(SPIDERS)?;
+    Command::cargo_bin(PRG)?
+        .args(["-z", "tests/inputs/members.txt.gz"])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn truncated_gzip_z() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([
+            "-z",
+            "tests/inputs/truncated.txt.gz",
+            "tests/inputs/fox.txt.gz",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with(
+            "tests/inputs/truncated.txt.gz: ",
+        ))
+        .stdout(predicate::str::ends_with(fs::read_to_string(FOX)?));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_nl_zero_padded() -> TestResult {
+    run(
+        &[
+            "--body-numbering",
+            "a",
+            "--number-format",
+            "rz",
+            "--number-width",
+            "3",
+            "--starting-line-number",
+            "-2",
+            "--line-increment",
+            "3",
+            "--number-separator",
+            ": ",
+            BUSTLE,
+        ],
+        "tests/expected/the-bustle.txt.nl-rz.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_nl_left() -> TestResult {
+    run(
+        &["-b", "--number-format", "ln", BUSTLE],
+        "tests/expected/the-bustle.txt.nl-ln.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_nl_regex() -> TestResult {
+    run(
+        &["--body-numbering", "p^The", BUSTLE],
+        "tests/expected/the-bustle.txt.nl-regex.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_nl_nonblank() -> TestResult {
+    run(
+        &["--body-numbering", "t", BUSTLE],
+        "tests/expected/the-bustle.txt.nl-t.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn all_n_no_renumber() -> TestResult {
+    run(
+        &["-n", "--no-renumber", FOX, SPIDERS, BUSTLE],
+        "tests/expected/all.n.no-renumber.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_number_width() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "--number-width", "0", FOX])
+        .assert()
+        .failure()
+        .stderr("invalid line number field width -- 0\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_to_lf() -> TestResult {
+    run(
+        &["--line-endings", "lf", "tests/inputs/crlf.txt"],
+        "tests/expected/crlf.txt.lf.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn cr_to_crlf_n() -> TestResult {
+    run(
+        &["--line-endings", "crlf", "-n", "tests/inputs/cr.txt"],
+        "tests/expected/cr.txt.crlf.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bom_strip() -> TestResult {
+    run(
+        &["--bom", "strip", "tests/inputs/bom.txt"],
+        "tests/expected/bom.txt.strip.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bom_add() -> TestResult {
+    run(
+        &["--bom", "add", "tests/expected/bom.txt.strip.out"],
+        "tests/expected/bom.txt.add.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bom_add_keeps_single_bom() -> TestResult {
+    run(
+        &["--bom", "add", "tests/inputs/bom.txt"],
+        "tests/expected/bom.txt.add.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn from_utf16le() -> TestResult {
+    run(
+        &["--from-encoding", "utf-16le", "tests/inputs/utf16le.txt"],
+        "tests/expected/utf16le.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn from_utf16le_lf_n() -> TestResult {
+    run(
+        &[
+            "--from-encoding=utf-16le",
+            "--line-endings=lf",
+            "-n",
+            "tests/inputs/utf16le.txt",
+        ],
+        "tests/expected/utf16le.txt.lf.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn from_utf16be() -> TestResult {
+    run(
+        &["--from-encoding", "utf-16be", "tests/inputs/utf16be.txt"],
+        "tests/expected/utf16be.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn from_windows_1252() -> TestResult {
+    run(
+        &[
+            "--from-encoding",
+            "windows-1252",
+            "tests/inputs/windows-1252.txt",
+        ],
+        "tests/expected/windows-1252.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unknown_encoding() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--from-encoding", "klingon", FOX])
+        .assert()
+        .failure()
+        .stderr("unknown encoding -- klingon\n");
+    Ok(())
+}
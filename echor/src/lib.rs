@@ -0,0 +1,219 @@
+use clap::{App, Arg};
+use std::error::Error;
+use std::io::{self, Write};
+
+type MyResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Debug)]
+pub struct Config {
+    text: Vec<String>,
+    omit_newline: bool,
+    escapes: bool,
+}
+
+pub fn get_args() -> MyResult<Config> {
+    let matches = App::new("echor")
+        .version("0.1.0")
+        .author("Tyson Liddell <tysonliddell@hotmail.com>")
+        .about("Rust echo")
+        .arg(
+            Arg::with_name("text")
+                .value_name("TEXT")
+                .help("Input text")
+                .multiple(true),
+        )
+        .arg(
+            Arg::with_name("omit_newline")
+                .short("n")
+                .help("Do not print newline")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("escapes")
+                .short("e")
+                .help("Enable interpretation of backslash escapes")
+                .takes_value(false)
+                .overrides_with("no_escapes"),
+        )
+        .arg(
+            Arg::with_name("no_escapes")
+                .short("E")
+                .help("Disable interpretation of backslash escapes (default)")
+                .takes_value(false)
+                .overrides_with("escapes"),
+        )
+        .get_matches();
+
+    Ok(Config {
+        text: matches.values_of_lossy("text").unwrap_or_default(),
+        omit_newline: matches.is_present("omit_newline"),
+        escapes: matches.is_present("escapes"),
+    })
+}
+
+// Reads up to `max_digits` digits in `radix` from the front of `chars`.
+fn take_digits(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    radix: u32,
+    max_digits: usize,
+) -> Option<u32> {
+    let mut value = None;
+    for _ in 0..max_digits {
+        match chars.peek().and_then(|c| c.to_digit(radix)) {
+            Some(digit) => {
+                value = Some(value.unwrap_or(0) * radix + digit);
+                chars.next();
+            }
+            None => break,
+        }
+    }
+    value
+}
+
+fn push_char(out: &mut Vec<u8>, c: char) {
+    let mut buf = [0; 4];
+    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+/// The two flavours of backslash escapes understood by echo and printf.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Escapes {
+    /// `echo -e` and printf's `%b`: octal is written `\0NNN` or `\NNN`.
+    Echo,
+    /// A printf format string: octal is `\NNN` and `\"` is a double quote.
+    Format,
+}
+
+/// Appends `text` to `out` with backslash escapes interpreted as by
+/// `echo -e` or printf. Returns `false` if a `\c` was found, after which no
+/// further output should be produced.
+pub fn unescape(text: &str, escapes: Escapes, out: &mut Vec<u8>) -> bool {
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            push_char(out, c);
+            continue;
+        }
+
+        // GNU echo also takes octal escapes without the leading zero, and
+        // in a printf format that zero counts as one of the three digits.
+        let first_octal = if escapes == Escapes::Echo { '1' } else { '0' };
+        if matches!(chars.peek(), Some(&c) if (first_octal..='7').contains(&c)) {
+            out.push(take_digits(&mut chars, 8, 3).unwrap_or(0) as u8);
+            continue;
+        }
+
+        let byte = match chars.next() {
+            Some('\\') => b'\\',
+            Some('"') if escapes == Escapes::Format => b'"',
+            Some('a') => 0x07,
+            Some('b') => 0x08,
+            Some('c') => return false,
+            Some('e') => 0x1b,
+            Some('f') => 0x0c,
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('v') => 0x0b,
+            Some('0') => take_digits(&mut chars, 8, 3).unwrap_or(0) as u8,
+            Some('x') => match take_digits(&mut chars, 16, 2) {
+                Some(value) => value as u8,
+                None => {
+                    out.extend_from_slice(b"\\x");
+                    continue;
+                }
+            },
+            Some(kind @ ('u' | 'U')) => {
+                let max_digits = if kind == 'u' { 4 } else { 8 };
+                match take_digits(&mut chars, 16, max_digits) {
+                    Some(value) => push_char(
+                        out,
+                        char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER),
+                    ),
+                    None => out.extend_from_slice(&[b'\\', kind as u8]),
+                }
+                continue;
+            }
+            // Like GNU echo, anything else is not an escape and is printed
+            // as is, backslash included.
+            Some(other) => {
+                out.push(b'\\');
+                push_char(out, other);
+                continue;
+            }
+            None => b'\\',
+        };
+        out.push(byte);
+    }
+    true
+}
+
+pub fn run(config: Config) -> MyResult<()> {
+    let text = config.text.join(" ");
+    let mut out = Vec::with_capacity(text.len() + 1);
+
+    let finished = if config.escapes {
+        unescape(&text, Escapes::Echo, &mut out)
+    } else {
+        out.extend_from_slice(text.as_bytes());
+        true
+    };
+    if finished && !config.omit_newline {
+        out.push(b'\n');
+    }
+
+    io::stdout().write_all(&out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unescape, Escapes};
+
+    fn unescaped(text: &str) -> (Vec<u8>, bool) {
+        let mut out = vec![];
+        let finished = unescape(text, Escapes::Echo, &mut out);
+        (out, finished)
+    }
+
+    #[test]
+    fn test_unescape() {
+        // Plain text is passed through
+        assert_eq!(unescaped("héllo"), ("héllo".as_bytes().to_vec(), true));
+
+        // Single-character escapes
+        assert_eq!(
+            unescaped(r"\a\b\e\f\n\r\t\v\\"),
+            (b"\x07\x08\x1b\x0c\n\r\t\x0b\\".to_vec(), true)
+        );
+
+        // Octal and hex escapes may produce bytes that are not UTF-8
+        assert_eq!(
+            unescaped(r"\0377\x7\xfff"),
+            (b"\xff\x07\xfff".to_vec(), true)
+        );
+        assert_eq!(unescaped(r"\0"), (b"\0".to_vec(), true));
+
+        // Unicode escapes are encoded as UTF-8
+        assert_eq!(unescaped(r"é\U0001F600"), ("é😀".as_bytes().to_vec(), true));
+
+        // Escapes without digits and unknown escapes are printed as is
+        assert_eq!(unescaped(r"\x\u\q"), (br"\x\u\q".to_vec(), true));
+        assert_eq!(unescaped("end\\"), (b"end\\".to_vec(), true));
+
+        // \c stops all further output
+        assert_eq!(unescaped(r"one\ctwo"), (b"one".to_vec(), false));
+    }
+
+    #[test]
+    fn test_unescape_format() {
+        let mut out = vec![];
+        assert!(unescape(r#"\0101\101\"\q"#, Escapes::Format, &mut out));
+        assert_eq!(out, b"\x081A\"\\q");
+
+        // Echo leaves \" alone and reads \0101 as a single escape
+        let mut out = vec![];
+        assert!(unescape(r#"\0101\""#, Escapes::Echo, &mut out));
+        assert_eq!(out, b"A\\\"");
+    }
+}
@@ -1,16 +1,11 @@
 use assert_cmd::Command;
-use predicates::prelude::*;
 use std::fs;
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
 #[test]
-fn dies_no_args() -> TestResult {
-    let mut cmd = Command::cargo_bin("echor")?;
-    cmd.assert()
-        .failure()
-        .stderr(predicate::str::contains("USAGE"));
-    Ok(())
+fn no_args() -> TestResult {
+    run(&[], "tests/expected/empty.txt")
 }
 
 fn run(args: &[&str], expected_file: &str) -> TestResult {
@@ -42,3 +37,29 @@ fn hello1_no_newline() -> TestResult {
 fn hello2_no_newline() -> TestResult {
     run(&["-n", "Hello", "there"], "tests/expected/hello2.n.txt")
 }
+
+#[test]
+fn escapes() -> TestResult {
+    run(
+        &["-e", r"a\tb\nc\\d \x41\0101\101 \q \xz"],
+        "tests/expected/escapes.e.txt",
+    )
+}
+
+#[test]
+fn escapes_stop_output() -> TestResult {
+    run(&["-e", r"keep\c gone"], "tests/expected/escapes.c.txt")
+}
+
+#[test]
+fn escapes_disabled() -> TestResult {
+    run(&["-e", "-E", r"a\tb"], "tests/expected/escapes.E.txt")
+}
+
+#[test]
+fn escapes_unicode() -> TestResult {
+    run(
+        &["-e", r"\u263a", r"\U0001F600"],
+        "tests/expected/escapes.u.txt",
+    )
+}
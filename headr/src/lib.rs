@@ -1,29 +1,183 @@
 use clap::{App, Arg};
+use echor::Escapes;
+use regex::bytes::Regex;
+use std::collections::VecDeque;
 use std::error::Error;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Seek, Write};
+use tailr::{LineIndex, LineRange, SizeError};
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
 #[derive(Debug)]
 pub struct Config {
     files: Vec<String>,
-    lines: usize,
-    bytes: Option<usize>,
+    lines: Count,
+    bytes: Option<Count>,
+    complete_chars: bool,
+    separator: Vec<u8>,
+    quiet: bool,
+    verbose: bool,
+    stop: Option<Stop>,
+    ranges: Vec<LineRange>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Count {
+    First(usize),
+    AllButLast(usize),
+}
+
+#[derive(Debug)]
+enum Stop {
+    Until { pattern: Regex, inclusive: bool },
+    While(Regex),
+}
+
+impl Stop {
+    // Returns `Some(print_line)` if output should stop at `line`.
+    fn ends_at(&self, line: &[u8]) -> Option<bool> {
+        match self {
+            Stop::Until { pattern, inclusive } if pattern.is_match(line) => Some(*inclusive),
+            Stop::While(pattern) if !pattern.is_match(line) => Some(false),
+            _ => None,
+        }
+    }
 }
 
 fn parse_positive_int(val: &str) -> MyResult<usize> {
-    // these doesn't quite work. Doesn't error on x == 0
-    // val.parse::<usize>().map_err(|_| From::from(val))
-    // or more concisely
-    // val.parse().map_err(|_| From::from(val))
-
-    match val.parse() {
-        Ok(n) if n > 0 => Ok(n),
-        _ => Err(From::from(val)),
+    parse_size(val, val)
+}
+
+// Parses `num`, which may have a multiplier suffix such as `K` or `MiB`,
+// reporting errors against the full value `val` given on the command line.
+fn parse_size(num: &str, val: &str) -> MyResult<usize> {
+    match tailr::parse_size(num) {
+        Ok(0) => Err(From::from(val)),
+        Ok(n) => usize::try_from(n).map_err(|_| SizeError::TooLarge.describe(val).into()),
+        Err(e) => Err(From::from(e.describe(val))),
+    }
+}
+
+fn parse_separator(val: &str) -> MyResult<Vec<u8>> {
+    let mut separator = Vec::new();
+    if !echor::unescape(val, Escapes::Format, &mut separator) || separator.is_empty() {
+        return Err(From::from(format!("invalid separator -- {}", val)));
+    }
+    Ok(separator)
+}
+
+fn parse_count(val: &str) -> MyResult<Count> {
+    match val.strip_prefix('-') {
+        Some(num) => parse_size(num, val).map(Count::AllButLast),
+        None => parse_positive_int(val).map(Count::First),
     }
 }
 
+#[test]
+fn test_parse_count() {
+    // A plain number takes the first lines/bytes
+    let res = parse_count("3");
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), Count::First(3));
+
+    // A leading "-" takes all but the last lines/bytes
+    let res = parse_count("-3");
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), Count::AllButLast(3));
+
+    // Errors report the whole value
+    let res = parse_count("-foo");
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(), "-foo".to_string());
+    let res = parse_count("--3");
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(), "--3".to_string());
+
+    // Multiplier suffixes are accepted either way
+    let res = parse_count("2KiB");
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), Count::First(2048));
+    let res = parse_count("-1MB");
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), Count::AllButLast(1_000_000));
+
+    // Overflow is reported rather than wrapped
+    let res = parse_count("-16E");
+    assert!(res.is_err());
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "-16E: value too large".to_string()
+    );
+}
+
+#[test]
+fn test_parse_separator() {
+    // Backslash escapes are interpreted
+    let res = parse_separator(r"%\n");
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), b"%\n");
+    let res = parse_separator(r"\0");
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), b"\0");
+
+    // An empty separator is an error
+    let res = parse_separator("");
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(), "invalid separator -- ");
+    let res = parse_separator(r"\c");
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_read_record() {
+    let mut file = io::Cursor::new("one%\ntwo%three%\n%\nfour");
+    let mut record = Vec::new();
+    let mut records = Vec::new();
+    while read_record(&mut file, b"%\n", &mut record).unwrap() > 0 {
+        records.push(String::from_utf8(std::mem::take(&mut record)).unwrap());
+    }
+    assert_eq!(records, ["one%\n", "two%three%\n", "%\n", "four"]);
+}
+
+#[test]
+fn test_stop_ends_at() {
+    let pattern = Regex::new("^ERROR").unwrap();
+    let until = Stop::Until {
+        pattern: pattern.clone(),
+        inclusive: false,
+    };
+    assert_eq!(until.ends_at(b"INFO ok"), None);
+    assert_eq!(until.ends_at(b"ERROR bad"), Some(false));
+
+    let until = Stop::Until {
+        pattern: pattern.clone(),
+        inclusive: true,
+    };
+    assert_eq!(until.ends_at(b"ERROR bad"), Some(true));
+
+    let while_ = Stop::While(pattern);
+    assert_eq!(while_.ends_at(b"ERROR bad"), None);
+    assert_eq!(while_.ends_at(b"INFO ok"), Some(false));
+}
+
+#[test]
+fn test_complete_chars_len() {
+    // ASCII and complete characters are left alone
+    assert_eq!(complete_chars_len(b""), 0);
+    assert_eq!(complete_chars_len(b"abc"), 3);
+    assert_eq!(complete_chars_len("aÖ€😀".as_bytes()), 10);
+
+    // A character cut short is dropped, whatever its length
+    assert_eq!(complete_chars_len(&"aÖ".as_bytes()[..2]), 1);
+    assert_eq!(complete_chars_len(&"a€".as_bytes()[..3]), 1);
+    assert_eq!(complete_chars_len(&"a😀".as_bytes()[..4]), 1);
+    assert_eq!(complete_chars_len(&"😀".as_bytes()[..1]), 0);
+
+    // Stray continuation bytes are not characters to trim
+    assert_eq!(complete_chars_len(b"a\x80\x80"), 3);
+}
+
 #[test]
 fn test_parse_positive_int() {
     // 3 is an OK integer
@@ -59,6 +213,7 @@ pub fn get_args() -> MyResult<Config> {
                 .short("n")
                 .long("lines")
                 .value_name("LINES")
+                .allow_hyphen_values(true)
                 .default_value("10")
                 .help("Number of lines"),
         )
@@ -67,30 +222,155 @@ pub fn get_args() -> MyResult<Config> {
                 .short("c")
                 .long("bytes")
                 .value_name("BYTES")
+                .allow_hyphen_values(true)
                 .help("Number of bytes")
                 .conflicts_with("lines"),
         )
+        .arg(
+            Arg::with_name("complete_chars")
+                .long("complete-chars")
+                .help("With -c, do not end partway through a UTF-8 character")
+                .requires("bytes"),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .alias("silent")
+                .help("Never print headers")
+                .overrides_with("verbose"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .help("Always print headers")
+                .overrides_with("quiet"),
+        )
+        .arg(
+            Arg::with_name("zero_terminated")
+                .short("z")
+                .long("zero-terminated")
+                .help("Records are terminated by NUL, not newline"),
+        )
+        .arg(
+            Arg::with_name("separator")
+                .long("separator")
+                .value_name("STR")
+                .help("Records are terminated by STR (backslash escapes allowed)")
+                .conflicts_with("zero_terminated"),
+        )
+        .arg(
+            Arg::with_name("until")
+                .long("until")
+                .value_name("REGEX")
+                .help("Stop before the first line matching REGEX")
+                .conflicts_with_all(&["bytes", "while"]),
+        )
+        .arg(
+            Arg::with_name("inclusive")
+                .long("inclusive")
+                .help("With --until, also print the matching line")
+                .requires("until"),
+        )
+        .arg(
+            Arg::with_name("while")
+                .long("while")
+                .value_name("REGEX")
+                .help("Print lines while they match REGEX")
+                .conflicts_with("bytes"),
+        )
+        .arg(
+            Arg::with_name("ranges")
+                .long("range")
+                .value_name("START:END")
+                .allow_hyphen_values(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Lines START to END, may be repeated")
+                .conflicts_with_all(&[
+                    "lines",
+                    "bytes",
+                    "until",
+                    "while",
+                    "zero_terminated",
+                    "separator",
+                ]),
+        )
         .get_matches();
 
-    let lines = matches
+    let mut lines = matches
         .value_of("lines")
-        .map(parse_positive_int)
+        .map(parse_count)
         .transpose()
         .map_err(|e| format!("illegal line count -- {}", e))?;
 
+    let parse_regex = |name| {
+        matches
+            .value_of(name)
+            .map(|v| Regex::new(v).map_err(|_| format!("Invalid --{} \"{}\"", name, v)))
+            .transpose()
+    };
+    let stop = match (parse_regex("until")?, parse_regex("while")?) {
+        (Some(pattern), _) => Some(Stop::Until {
+            pattern,
+            inclusive: matches.is_present("inclusive"),
+        }),
+        (_, Some(pattern)) => Some(Stop::While(pattern)),
+        _ => None,
+    };
+    if stop.is_some() {
+        // A pattern is only capped by -n when one is given explicitly
+        if matches.occurrences_of("lines") == 0 {
+            lines = Some(Count::First(usize::MAX));
+        } else if let Some(Count::AllButLast(_)) = lines {
+            return Err(From::from(
+                "a negative line count cannot be combined with --until or --while",
+            ));
+        }
+    }
+
     let bytes = matches
         .value_of("bytes")
-        .map(parse_positive_int)
+        .map(parse_count)
         .transpose()
         .map_err(|e| format!("illegal byte count -- {}", e))?;
 
+    let separator = match matches.value_of("separator") {
+        Some(val) => parse_separator(val)?,
+        None if matches.is_present("zero_terminated") => vec![b'\0'],
+        None => vec![b'\n'],
+    };
+
+    let ranges = matches
+        .values_of("ranges")
+        .unwrap_or_default()
+        .map(tailr::parse_range)
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("illegal line range -- {}", e))?;
+
     Ok(Config {
         files: matches.values_of_lossy("files").unwrap(),
         lines: lines.unwrap(),
         bytes,
+        complete_chars: matches.is_present("complete_chars"),
+        separator,
+        quiet: matches.is_present("quiet"),
+        verbose: matches.is_present("verbose"),
+        stop,
+        ranges,
     })
 }
 
+trait SeekBufRead: BufRead + Seek {}
+
+impl<T: BufRead + Seek> SeekBufRead for T {}
+
+enum Input {
+    Stream(Box<dyn BufRead>),
+    Seekable(Box<dyn SeekBufRead>),
+}
+
 fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
     match filename {
         "-" => Ok(Box::new(BufReader::new(io::stdin()))),
@@ -98,43 +378,201 @@ fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
     }
 }
 
-fn print_lines(mut file: Box<dyn BufRead>, num_lines: usize) -> MyResult<()> {
-    let mut buf = String::new();
+// Opens `filename` for random access. Stdin cannot seek, so it is copied to
+// a temporary file first; run only does this when the ranges cannot be
+// streamed.
+fn open_seekable(filename: &str) -> MyResult<Box<dyn SeekBufRead>> {
+    match filename {
+        "-" => Ok(Box::new(BufReader::new(tailr::spool(io::stdin())?))),
+        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+    }
+}
+
+// Appends the next record, including its separator, to `record`. Returns the
+// number of bytes read, which is zero at the end of the input.
+fn read_record(
+    file: &mut impl BufRead,
+    separator: &[u8],
+    record: &mut Vec<u8>,
+) -> io::Result<usize> {
+    let start = record.len();
+    let last = separator[separator.len() - 1];
+    loop {
+        if file.read_until(last, record)? == 0 || record[start..].ends_with(separator) {
+            return Ok(record.len() - start);
+        }
+    }
+}
+
+fn print_lines(
+    mut file: impl BufRead,
+    num_lines: usize,
+    separator: &[u8],
+    stop: Option<&Stop>,
+) -> MyResult<()> {
+    let mut out = io::stdout().lock();
+    let mut line = Vec::new();
     for _ in 0..num_lines {
-        if file.read_line(&mut buf)? == 0 {
+        if read_record(&mut file, separator, &mut line)? == 0 {
+            break;
+        }
+        let text = line.strip_suffix(separator).unwrap_or(&line);
+        if let Some(print_line) = stop.and_then(|stop| stop.ends_at(text)) {
+            if print_line {
+                out.write_all(&line)?;
+            }
+            break;
+        }
+        out.write_all(&line)?;
+        line.clear();
+    }
+    Ok(())
+}
+
+// Prints all but the last `num_lines` lines, holding that many lines back in
+// a ring buffer until we know they are not at the end of the input.
+fn print_lines_but_last(
+    mut file: impl BufRead,
+    num_lines: usize,
+    separator: &[u8],
+) -> MyResult<()> {
+    let mut out = io::stdout().lock();
+    let mut held = VecDeque::new();
+    loop {
+        let mut line = Vec::new();
+        if read_record(&mut file, separator, &mut line)? == 0 {
             break;
         }
-        print!("{}", buf);
-        buf.clear();
+        held.push_back(line);
+        if held.len() > num_lines {
+            out.write_all(&held.pop_front().unwrap())?;
+        }
     }
     Ok(())
 }
 
-fn print_bytes(file: Box<dyn BufRead>, num_bytes: usize) -> MyResult<()> {
-    let bytes: Result<Vec<_>, _> = file.bytes().take(num_bytes).collect();
-    print!("{}", String::from_utf8_lossy(&bytes?));
+// Returns the length of `bytes` without any UTF-8 character that is left
+// incomplete at the end. Such a character starts at most three bytes back.
+fn complete_chars_len(bytes: &[u8]) -> usize {
+    let start = bytes.len().saturating_sub(3);
+    for (i, &byte) in bytes.iter().enumerate().skip(start).rev() {
+        let char_len = match byte {
+            0x80..=0xbf => continue,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        return if i + char_len > bytes.len() {
+            i
+        } else {
+            bytes.len()
+        };
+    }
+    bytes.len()
+}
+
+// Writes everything read from `file` except for the last `held_back` bytes.
+// If `complete_chars` is set, an incomplete UTF-8 character at the end of the
+// output is held back as well.
+fn write_held_back(mut file: impl BufRead, held_back: usize, complete_chars: bool) -> MyResult<()> {
+    let mut out = io::stdout().lock();
+    // A few extra bytes are needed to find the start of the last character
+    let reserve = held_back + if complete_chars { 3 } else { 0 };
+    let mut held = VecDeque::new();
+    loop {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        held.extend(buf);
+        let len = buf.len();
+        file.consume(len);
+
+        if held.len() > reserve {
+            let excess = held.len() - reserve;
+            let (front, back) = held.as_slices();
+            let front_len = front.len().min(excess);
+            out.write_all(&front[..front_len])?;
+            out.write_all(&back[..excess - front_len])?;
+            held.drain(..excess);
+        }
+    }
+
+    let rest = held.len().saturating_sub(held_back);
+    let rest: Vec<_> = held.drain(..rest).collect();
+    let rest_len = if complete_chars {
+        complete_chars_len(&rest)
+    } else {
+        rest.len()
+    };
+    out.write_all(&rest[..rest_len])?;
     Ok(())
 }
 
+fn print_bytes(file: impl BufRead, num_bytes: usize, complete_chars: bool) -> MyResult<()> {
+    write_held_back(file.take(num_bytes as u64), 0, complete_chars)
+}
+
+// Prints all but the last `num_bytes` bytes, holding that many bytes back in
+// a ring buffer until we know they are not at the end of the input.
+fn print_bytes_but_last(
+    file: impl BufRead,
+    num_bytes: usize,
+    complete_chars: bool,
+) -> MyResult<()> {
+    write_held_back(file, num_bytes, complete_chars)
+}
+
 pub fn run(config: Config) -> MyResult<()> {
     let mut file_succeeded = false;
-    let print_header = config.files.len() > 1;
+    let print_header = config.verbose || (!config.quiet && config.files.len() > 1);
 
     for filename in &config.files {
-        match open(filename) {
+        let streamed = filename == "-" && tailr::can_stream_ranges(&config.ranges);
+        let input = if config.ranges.is_empty() || streamed {
+            open(filename).map(Input::Stream)
+        } else {
+            open_seekable(filename).map(Input::Seekable)
+        };
+        match input {
             Err(e) => eprintln!("{}: {}", filename, e),
-            Ok(file) => {
+            Ok(input) => {
                 if print_header {
                     println!(
                         "{}==> {} <==",
                         if file_succeeded { "\n" } else { "" },
-                        filename
+                        if filename == "-" {
+                            "standard input"
+                        } else {
+                            filename
+                        }
                     );
                 }
-                if let Some(num_bytes) = config.bytes {
-                    print_bytes(file, num_bytes)?;
-                } else {
-                    print_lines(file, config.lines)?;
+                match input {
+                    Input::Seekable(mut file) => {
+                        let mut index = LineIndex::default();
+                        for range in &config.ranges {
+                            tailr::print_range(&mut file, &mut index, *range)?;
+                        }
+                    }
+                    Input::Stream(mut file) if !config.ranges.is_empty() => {
+                        tailr::print_ranges_stream(&mut file, &config.ranges)?
+                    }
+                    Input::Stream(file) => match (config.bytes, config.lines) {
+                        (Some(Count::First(num)), _) => {
+                            print_bytes(file, num, config.complete_chars)?
+                        }
+                        (Some(Count::AllButLast(num)), _) => {
+                            print_bytes_but_last(file, num, config.complete_chars)?
+                        }
+                        (None, Count::First(num)) => {
+                            print_lines(file, num, &config.separator, config.stop.as_ref())?
+                        }
+                        (None, Count::AllButLast(num)) => {
+                            print_lines_but_last(file, num, &config.separator)?
+                        }
+                    },
                 }
                 file_succeeded = true;
             }
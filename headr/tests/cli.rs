@@ -1,11 +1,7 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
-use std::io::prelude::*;
-use std::{
-    error::Error,
-    fs::{self, File},
-};
+use std::{error::Error, fs, io::Write, process::Stdio, thread, time::Duration};
 
 type TestResult = Result<(), Box<dyn Error>>;
 
@@ -15,6 +11,11 @@ const ONE: &str = "./tests/inputs/one.txt";
 const TWO: &str = "./tests/inputs/two.txt";
 const THREE: &str = "./tests/inputs/three.txt";
 const TEN: &str = "./tests/inputs/ten.txt";
+const BINARY: &str = "./tests/inputs/binary.bin";
+const NUL: &str = "./tests/inputs/nul.txt";
+const FORTUNES: &str = "./tests/inputs/fortunes.txt";
+const PARAGRAPHS: &str = "./tests/inputs/paragraphs.txt";
+const LOG: &str = "./tests/inputs/log.txt";
 
 // --------------------------------------------------
 fn random_string() -> String {
@@ -41,7 +42,7 @@ fn dies_bad_bytes() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal byte count -- {}", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-c", &bad, EMPTY])
+        .args(["-c", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -55,7 +56,7 @@ fn dies_bad_lines() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal line count -- {}", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-n", &bad, EMPTY])
+        .args(["-n", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -63,6 +64,20 @@ fn dies_bad_lines() -> TestResult {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_too_large() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "16E", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "illegal byte count -- 16E: value too large",
+        ));
+
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn dies_bytes_and_lines() -> TestResult {
@@ -70,7 +85,7 @@ fn dies_bytes_and_lines() -> TestResult {
                used with '--bytes <BYTES>'";
 
     Command::cargo_bin(PRG)?
-        .args(&["-n", "1", "-c", "2"])
+        .args(["-n", "1", "-c", "2"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(msg));
@@ -93,39 +108,27 @@ fn skips_bad_file() -> TestResult {
 
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
+    let expected = fs::read(expected_file)?;
 
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
         .success()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(predicate::eq(expected));
 
     Ok(())
 }
 
 // --------------------------------------------------
-fn run_stdin(
-    args: &[&str],
-    input_file: &str,
-    expected_file: &str,
-) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
-    let input = fs::read_to_string(input_file)?;
+fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
+    let input = fs::read(input_file)?;
 
     Command::cargo_bin(PRG)?
         .write_stdin(input)
         .args(args)
         .assert()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(predicate::eq(expected));
 
     Ok(())
 }
@@ -419,3 +422,433 @@ fn multiple_files_c4() -> TestResult {
         "tests/expected/all.c4.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn empty_n_minus2() -> TestResult {
+    run(&[EMPTY, "-n", "-2"], "tests/expected/empty.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn empty_n_minus2_stdin() -> TestResult {
+    run_stdin(&["-n", "-2"], EMPTY, "tests/expected/empty.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn two_n_minus_huge() -> TestResult {
+    // The held lines grow with the input, not with the count
+    for count in ["-1G", "-1E"] {
+        Command::cargo_bin(PRG)?
+            .args(["-n", count])
+            .write_stdin(fs::read(TWO)?)
+            .assert()
+            .success()
+            .stdout("");
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn empty_c_minus4() -> TestResult {
+    run(&[EMPTY, "-c", "-4"], "tests/expected/empty.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn empty_c_minus4_stdin() -> TestResult {
+    run_stdin(&["-c", "-4"], EMPTY, "tests/expected/empty.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn one_n_minus2() -> TestResult {
+    run(&[ONE, "-n", "-2"], "tests/expected/one.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn one_n_minus2_stdin() -> TestResult {
+    run_stdin(&["-n", "-2"], ONE, "tests/expected/one.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn one_c_minus4() -> TestResult {
+    run(&[ONE, "-c", "-4"], "tests/expected/one.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn one_c_minus4_stdin() -> TestResult {
+    run_stdin(&["-c", "-4"], ONE, "tests/expected/one.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn two_n_minus2() -> TestResult {
+    run(&[TWO, "-n", "-2"], "tests/expected/two.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn two_n_minus2_stdin() -> TestResult {
+    run_stdin(&["-n", "-2"], TWO, "tests/expected/two.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn two_c_minus4() -> TestResult {
+    run(&[TWO, "-c", "-4"], "tests/expected/two.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn two_c_minus4_stdin() -> TestResult {
+    run_stdin(&["-c", "-4"], TWO, "tests/expected/two.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn three_n_minus2() -> TestResult {
+    run(&[THREE, "-n", "-2"], "tests/expected/three.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn three_n_minus2_stdin() -> TestResult {
+    run_stdin(&["-n", "-2"], THREE, "tests/expected/three.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn three_c_minus4() -> TestResult {
+    run(&[THREE, "-c", "-4"], "tests/expected/three.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn three_c_minus4_stdin() -> TestResult {
+    run_stdin(&["-c", "-4"], THREE, "tests/expected/three.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_n_minus2() -> TestResult {
+    run(&[TEN, "-n", "-2"], "tests/expected/ten.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_n_minus2_stdin() -> TestResult {
+    run_stdin(&["-n", "-2"], TEN, "tests/expected/ten.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_c_minus4() -> TestResult {
+    run(&[TEN, "-c", "-4"], "tests/expected/ten.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_c_minus4_stdin() -> TestResult {
+    run_stdin(&["-c", "-4"], TEN, "tests/expected/ten.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_n_minus2() -> TestResult {
+    run(
+        &[EMPTY, ONE, TWO, THREE, TEN, "-n", "-2"],
+        "tests/expected/all.n-2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_c_minus4() -> TestResult {
+    run(
+        &[EMPTY, ONE, TWO, THREE, TEN, "-c", "-4"],
+        "tests/expected/all.c-4.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_c_suffix() -> TestResult {
+    run(&[TEN, "-c", "1K"], "tests/expected/ten.txt.c1K.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_n_minus_suffix() -> TestResult {
+    run(&[TEN, "-n", "-1b"], "tests/expected/ten.txt.n-1b.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_c100() -> TestResult {
+    run(&[BINARY, "-c", "100"], "tests/expected/binary.bin.c100.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_c_minus_8_stdin() -> TestResult {
+    run_stdin(&["-c", "-8"], BINARY, "tests/expected/binary.bin.c-8.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn one_c1_complete_chars() -> TestResult {
+    run(
+        &[ONE, "-c", "1", "--complete-chars"],
+        "tests/expected/one.txt.c1.complete.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn one_c2_complete_chars() -> TestResult {
+    run(
+        &[ONE, "-c", "2", "--complete-chars"],
+        "tests/expected/one.txt.c2.complete.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn one_c_minus_22_complete_chars_stdin() -> TestResult {
+    run_stdin(
+        &["-c", "-22", "--complete-chars"],
+        ONE,
+        "tests/expected/one.txt.c-22.complete.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_complete_chars_without_bytes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([ONE, "--complete-chars"])
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_quiet() -> TestResult {
+    run(&[ONE, TWO, "-n", "2", "-q"], "tests/expected/all.n2.q.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn one_verbose() -> TestResult {
+    run(&[ONE, "-v"], "tests/expected/one.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn quiet_overridden_by_verbose() -> TestResult {
+    run(&[ONE, "-q", "-v"], "tests/expected/one.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn one_verbose_stdin() -> TestResult {
+    run_stdin(&["-v", "-"], ONE, "tests/expected/one.txt.v.stdin.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nul_z_n2() -> TestResult {
+    run(&[NUL, "-z", "-n", "2"], "tests/expected/nul.txt.z.n2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nul_z_n_minus_1_stdin() -> TestResult {
+    run_stdin(&["-z", "-n", "-1"], NUL, "tests/expected/nul.txt.z.n-1.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn fortunes_separator_n2() -> TestResult {
+    run(
+        &[FORTUNES, "--separator", r"%\n", "-n", "2"],
+        "tests/expected/fortunes.txt.sep.n2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn paragraphs_separator_n_minus_1() -> TestResult {
+    run(
+        &[PARAGRAPHS, "--separator", r"\n\n", "-n", "-1"],
+        "tests/expected/paragraphs.txt.sep.n-1.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_empty_separator() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([ONE, "--separator", ""])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid separator"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn log_until() -> TestResult {
+    run(
+        &[LOG, "--until", "^ERROR"],
+        "tests/expected/log.txt.until.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn log_until_inclusive() -> TestResult {
+    run(
+        &[LOG, "--until", "^ERROR", "--inclusive"],
+        "tests/expected/log.txt.until.inclusive.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn log_until_capped_by_n() -> TestResult {
+    run(
+        &[LOG, "--until", "^ERROR", "-n", "4"],
+        "tests/expected/log.txt.until.n4.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn log_until_blank_stdin() -> TestResult {
+    run_stdin(
+        &["--until", "^$"],
+        LOG,
+        "tests/expected/log.txt.until_blank.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn log_while() -> TestResult {
+    run(&[LOG, "--while", "^#"], "tests/expected/log.txt.while.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_until() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([LOG, "--until", "*"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#"Invalid --until "*""#));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_until_negative_lines() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([LOG, "--until", "^$", "-n", "-2"])
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_range() -> TestResult {
+    run(
+        &[TEN, "--range", "3:5"],
+        "tests/expected/ten.txt.range3-5.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_ranges_stdin() -> TestResult {
+    run_stdin(
+        &["--range", "-3:", "--range", ":1"],
+        TEN,
+        "tests/expected/ten.txt.ranges.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_ordered_ranges_stdin() -> TestResult {
+    run_stdin(
+        &["--range", ":2", "--range", "5:6"],
+        TEN,
+        "tests/expected/ten.txt.ranges-ordered.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn range_streams_stdin() -> TestResult {
+    // Stdin is never closed, so this only finishes if the range is printed
+    // without waiting for the end of the input
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .args(["--range", "2:3"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"one\ntwo\nthree\nfour\n")?;
+
+    for _ in 0..100 {
+        if child.try_wait()?.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    if child.try_wait()?.is_none() {
+        child.kill()?;
+        panic!("headr waited for the end of stdin");
+    }
+
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "two\nthree\n");
+    drop(stdin);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_range() -> TestResult {
+    run(
+        &[EMPTY, ONE, TWO, THREE, TEN, "--range", "2:"],
+        "tests/expected/all.range2-.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_range() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([TEN, "--range", "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("illegal line range -- 3"));
+
+    Ok(())
+}
@@ -0,0 +1,739 @@
+use clap::{App, AppSettings, Arg};
+use echor::{unescape, Escapes};
+use std::error::Error;
+use std::io::{self, Write};
+
+type MyResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Debug)]
+pub struct Config {
+    format: String,
+    args: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Flags {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alternate: bool,
+    zero: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Count {
+    Fixed(usize),
+    FromArg,
+}
+
+#[derive(Debug, PartialEq)]
+struct Spec {
+    flags: Flags,
+    width: Option<Count>,
+    precision: Option<Count>,
+    conversion: char,
+}
+
+#[derive(Debug, PartialEq)]
+enum Piece {
+    Text(String),
+    Spec(Spec),
+}
+
+pub fn get_args() -> MyResult<Config> {
+    let matches = App::new("printfr")
+        .version("0.1.0")
+        .author("Tyson Liddell <tysonliddell@hotmail.com>")
+        .about("Rust printf")
+        .setting(AppSettings::TrailingVarArg)
+        .setting(AppSettings::AllowLeadingHyphen)
+        .arg(
+            Arg::with_name("format")
+                .value_name("FORMAT")
+                .required(true)
+                .allow_hyphen_values(true)
+                .help("Format string"),
+        )
+        .arg(
+            Arg::with_name("args")
+                .value_name("ARGUMENT")
+                .multiple(true)
+                .allow_hyphen_values(true)
+                .help("Values to format"),
+        )
+        .get_matches();
+
+    Ok(Config {
+        format: matches.value_of_lossy("format").unwrap().into_owned(),
+        args: matches.values_of_lossy("args").unwrap_or_default(),
+    })
+}
+
+fn parse_format(format: &str) -> MyResult<Vec<Piece>> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = format.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            // An escaped character can never start a directive
+            if c == '\\' {
+                text.extend(chars.next().map(|(_, c)| c));
+            }
+            continue;
+        }
+
+        if let Some((_, '%')) = chars.peek() {
+            chars.next();
+            text.push('%');
+            continue;
+        }
+
+        let mut flags = Flags::default();
+        while let Some(&(_, c)) = chars.peek() {
+            match c {
+                '-' => flags.left = true,
+                '+' => flags.plus = true,
+                ' ' => flags.space = true,
+                '#' => flags.alternate = true,
+                '0' => flags.zero = true,
+                // Digit grouping is a no-op in the C locale
+                '\'' => {}
+                _ => break,
+            }
+            chars.next();
+        }
+
+        let take_count = |chars: &mut std::iter::Peekable<std::str::CharIndices>| {
+            if let Some((_, '*')) = chars.peek() {
+                chars.next();
+                return Some(Count::FromArg);
+            }
+            let mut digits = String::new();
+            while let Some(&(_, c)) = chars.peek().filter(|(_, c)| c.is_ascii_digit()) {
+                digits.push(c);
+                chars.next();
+            }
+            digits.parse().ok().map(Count::Fixed)
+        };
+
+        let width = take_count(&mut chars);
+        let precision = match chars.peek() {
+            Some((_, '.')) => {
+                chars.next();
+                Some(take_count(&mut chars).unwrap_or(Count::Fixed(0)))
+            }
+            _ => None,
+        };
+
+        // Length modifiers mean nothing here, as in GNU printf
+        while let Some((_, 'h' | 'l' | 'L' | 'j' | 'z' | 't')) = chars.peek() {
+            chars.next();
+        }
+
+        // Like GNU printf, reject flags that make no sense for the conversion
+        let is_valid = |conversion| match conversion {
+            'b' | 'q' => flags == Flags::default() && width.is_none() && precision.is_none(),
+            'c' => !flags.alternate && !flags.zero && precision.is_none(),
+            's' => !flags.alternate && !flags.zero,
+            'd' | 'i' | 'u' => !flags.alternate,
+            _ => "oxXfFeEgG".contains(conversion),
+        };
+        match chars.next() {
+            Some((_, conversion)) if is_valid(conversion) => {
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Spec(Spec {
+                    flags,
+                    width,
+                    precision,
+                    conversion,
+                }));
+            }
+            next => {
+                let end = next.map_or(format.len(), |(i, c)| i + c.len_utf8());
+                return Err(From::from(format!(
+                    "{}: invalid conversion specification",
+                    &format[start..end]
+                )));
+            }
+        }
+    }
+
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+// A numeric argument split into sign and magnitude, as read by strtoimax.
+#[derive(Debug, PartialEq)]
+struct Integer {
+    negative: bool,
+    magnitude: u128,
+}
+
+const NOT_NUMERIC: &str = "expected a numeric value";
+const NOT_CONVERTED: &str = "value not completely converted";
+const OUT_OF_RANGE: &str = "Numerical result out of range";
+
+// Reads a decimal, octal (leading 0) or hex (leading 0x) integer, or the
+// code point of the character after a leading quote. On failure the value
+// that was read is returned along with the reason.
+fn parse_integer(arg: &str) -> (Integer, Option<&'static str>) {
+    let s = arg.trim_start();
+    let mut chars = s.chars();
+    if let Some('\'' | '"') = chars.next() {
+        let magnitude = chars.next().map_or(0, u128::from);
+        return (
+            Integer {
+                negative: false,
+                magnitude,
+            },
+            None,
+        );
+    }
+
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (radix, digits) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) if hex.starts_with(|c: char| c.is_ascii_hexdigit()) => (16, hex),
+        _ if s.starts_with('0') => (8, s),
+        _ => (10, s),
+    };
+
+    let len = digits
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or(digits.len());
+    let mut magnitude: u128 = 0;
+    let mut overflow = false;
+    for c in digits[..len].chars() {
+        let digit = c.to_digit(radix).unwrap();
+        match magnitude
+            .checked_mul(radix.into())
+            .and_then(|m| m.checked_add(digit.into()))
+        {
+            Some(m) => magnitude = m,
+            None => overflow = true,
+        }
+    }
+
+    let issue = if len == 0 {
+        Some(NOT_NUMERIC)
+    } else if len < digits.len() {
+        Some(NOT_CONVERTED)
+    } else if overflow {
+        Some(OUT_OF_RANGE)
+    } else {
+        None
+    };
+    let magnitude = if overflow { u128::MAX } else { magnitude };
+    (
+        Integer {
+            negative: negative && magnitude > 0,
+            magnitude,
+        },
+        issue,
+    )
+}
+
+fn to_signed(int: &Integer) -> (i64, Option<&'static str>) {
+    let max = i64::MAX as u128;
+    match (int.negative, int.magnitude) {
+        (false, m) if m > max => (i64::MAX, Some(OUT_OF_RANGE)),
+        (true, m) if m > max + 1 => (i64::MIN, Some(OUT_OF_RANGE)),
+        (false, m) => (m as i64, None),
+        (true, m) => ((m as i64).wrapping_neg(), None),
+    }
+}
+
+// Like strtoumax, negative values wrap around.
+fn to_unsigned(int: &Integer) -> (u64, Option<&'static str>) {
+    match int.magnitude.try_into() {
+        Ok(m) if int.negative => (u64::wrapping_neg(m), None),
+        Ok(m) => (m, None),
+        Err(_) => (u64::MAX, Some(OUT_OF_RANGE)),
+    }
+}
+
+fn parse_float(arg: &str) -> (f64, Option<&'static str>) {
+    let s = arg.trim_start();
+    if let Ok(value) = s.parse() {
+        return (value, None);
+    }
+    if let (int, None) = parse_integer(s) {
+        let value = int.magnitude as f64;
+        return (if int.negative { -value } else { value }, None);
+    }
+
+    // Fall back to the longest prefix that makes a number
+    (1..s.len())
+        .rev()
+        .filter(|&end| s.is_char_boundary(end))
+        .find_map(|end| s[..end].parse().ok())
+        .map_or((0.0, Some(NOT_NUMERIC)), |value| {
+            (value, Some(NOT_CONVERTED))
+        })
+}
+
+// Splits Rust's `1.5e-7` style into the mantissa and exponent.
+fn exp_parts(value: f64, precision: usize) -> (String, i32) {
+    let s = format!("{:.*e}", precision, value);
+    let (mantissa, exp) = s.split_once('e').unwrap();
+    (mantissa.to_string(), exp.parse().unwrap())
+}
+
+fn format_exp(mantissa: &str, exp: i32, upper: bool) -> String {
+    let e = if upper { 'E' } else { 'e' };
+    let sign = if exp < 0 { '-' } else { '+' };
+    format!("{}{}{}{:02}", mantissa, e, sign, exp.abs())
+}
+
+fn strip_trailing_zeros(s: &str) -> &str {
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        s
+    }
+}
+
+// Formats the absolute value of a finite float for %f, %e or %g.
+fn format_float(value: f64, conversion: char, precision: usize, alternate: bool) -> String {
+    let upper = conversion.is_ascii_uppercase();
+    let mut body = match conversion.to_ascii_lowercase() {
+        'e' => {
+            let (mantissa, exp) = exp_parts(value, precision);
+            format_exp(&mantissa, exp, upper)
+        }
+        'g' => {
+            // The exponent decides the style, after rounding to precision
+            let precision = precision.max(1);
+            let (mantissa, exp) = exp_parts(value, precision - 1);
+            if exp < -4 || exp >= precision as i32 {
+                let mantissa = if alternate {
+                    &mantissa
+                } else {
+                    strip_trailing_zeros(&mantissa)
+                };
+                format_exp(mantissa, exp, upper)
+            } else {
+                let fixed = format!("{:.*}", (precision as i32 - 1 - exp) as usize, value);
+                if alternate {
+                    fixed
+                } else {
+                    strip_trailing_zeros(&fixed).to_string()
+                }
+            }
+        }
+        _ => format!("{:.*}", precision, value),
+    };
+    if alternate && !body.contains('.') {
+        let at = body.find(['e', 'E']).unwrap_or(body.len());
+        body.insert(at, '.');
+    }
+    body
+}
+
+// Characters that never need quoting for the shell. Like GNU printf, `~`
+// and `#` only need it at the start of a word.
+fn is_shell_safe(c: char, first: bool) -> bool {
+    c.is_alphanumeric()
+        || (!c.is_ascii() && !c.is_control())
+        || "%+,-./:@_{}".contains(c)
+        || (!first && "~#".contains(c))
+}
+
+fn shell_quote(arg: &str) -> String {
+    if arg.is_empty() {
+        return "''".to_string();
+    }
+    if arg
+        .chars()
+        .enumerate()
+        .all(|(i, c)| is_shell_safe(c, i == 0))
+    {
+        return arg.to_string();
+    }
+    if arg.contains('\'') && !arg.contains(|c: char| c.is_control() || "\"$`\\!".contains(c)) {
+        return format!("\"{}\"", arg);
+    }
+
+    // Control characters are written with $'...' between quoted runs
+    let mut quoted = String::from("'");
+    for c in arg.chars() {
+        match c {
+            '\'' => quoted.push_str("'\\''"),
+            c if c.is_control() => {
+                let escape = match c {
+                    '\x07' => "\\a".to_string(),
+                    '\x08' => "\\b".to_string(),
+                    '\x0c' => "\\f".to_string(),
+                    '\n' => "\\n".to_string(),
+                    '\r' => "\\r".to_string(),
+                    '\t' => "\\t".to_string(),
+                    '\x0b' => "\\v".to_string(),
+                    _ => format!("\\{:03o}", c as u32),
+                };
+                quoted.push_str(&format!("'$'{}''", escape));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+fn pad(out: &mut Vec<u8>, body: &[u8], width: usize, left: bool) {
+    let fill = width.saturating_sub(body.len());
+    if !left {
+        out.resize(out.len() + fill, b' ');
+    }
+    out.extend_from_slice(body);
+    if left {
+        out.resize(out.len() + fill, b' ');
+    }
+}
+
+// Pads a number to `width`, putting any zero padding between the sign or
+// base prefix and the digits.
+fn pad_number(out: &mut Vec<u8>, prefix: &str, digits: &str, width: usize, flags: &Flags) {
+    if flags.zero && !flags.left {
+        let fill = width.saturating_sub(prefix.len() + digits.len());
+        let zeros = "0".repeat(fill);
+        pad(
+            out,
+            format!("{}{}{}", prefix, zeros, digits).as_bytes(),
+            0,
+            false,
+        );
+    } else {
+        pad(
+            out,
+            format!("{}{}", prefix, digits).as_bytes(),
+            width,
+            flags.left,
+        );
+    }
+}
+
+struct Printer<'a> {
+    args: &'a [String],
+    used: usize,
+    out: Vec<u8>,
+    ok: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn next_arg(&mut self) -> Option<&'a str> {
+        let arg = self.args.get(self.used)?;
+        self.used += 1;
+        Some(arg)
+    }
+
+    fn report(&mut self, arg: &str, issue: Option<&str>) {
+        if let Some(issue) = issue {
+            eprintln!("'{}': {}", arg, issue);
+            self.ok = false;
+        }
+    }
+
+    fn next_signed(&mut self) -> i64 {
+        self.next_arg().map_or(0, |arg| {
+            let (int, issue) = parse_integer(arg);
+            let (value, range_issue) = to_signed(&int);
+            self.report(arg, issue.or(range_issue));
+            value
+        })
+    }
+
+    fn next_unsigned(&mut self) -> u64 {
+        self.next_arg().map_or(0, |arg| {
+            let (int, issue) = parse_integer(arg);
+            let (value, range_issue) = to_unsigned(&int);
+            self.report(arg, issue.or(range_issue));
+            value
+        })
+    }
+
+    fn next_float(&mut self) -> f64 {
+        self.next_arg().map_or(0.0, |arg| {
+            let (value, issue) = parse_float(arg);
+            self.report(arg, issue);
+            value
+        })
+    }
+
+    fn count(&mut self, count: &Option<Count>) -> Option<i64> {
+        match count {
+            Some(Count::Fixed(n)) => Some(*n as i64),
+            Some(Count::FromArg) => Some(self.next_signed()),
+            None => None,
+        }
+    }
+
+    // Prints one directive. Returns `false` if a `\c` in a %b argument
+    // means that nothing more should be printed.
+    fn print_spec(&mut self, spec: &Spec) -> bool {
+        let mut flags = spec.flags;
+        let width = match self.count(&spec.width) {
+            Some(w) if w < 0 => {
+                flags.left = true;
+                w.unsigned_abs() as usize
+            }
+            w => w.unwrap_or(0) as usize,
+        };
+        let precision = self
+            .count(&spec.precision)
+            .and_then(|p| usize::try_from(p).ok());
+
+        match spec.conversion {
+            'd' | 'i' => {
+                let value = self.next_signed();
+                let sign = if value < 0 {
+                    "-"
+                } else if flags.plus {
+                    "+"
+                } else if flags.space {
+                    " "
+                } else {
+                    ""
+                };
+                self.print_integer(sign, value.unsigned_abs(), 'u', width, precision, &flags);
+            }
+            'o' | 'u' | 'x' | 'X' => {
+                let value = self.next_unsigned();
+                let prefix = match spec.conversion {
+                    'x' if flags.alternate && value != 0 => "0x",
+                    'X' if flags.alternate && value != 0 => "0X",
+                    _ => "",
+                };
+                self.print_integer(prefix, value, spec.conversion, width, precision, &flags);
+            }
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' => {
+                let value = self.next_float();
+                let sign = if value.is_sign_negative() {
+                    "-"
+                } else if flags.plus {
+                    "+"
+                } else if flags.space {
+                    " "
+                } else {
+                    ""
+                };
+                if value.is_finite() {
+                    let body = format_float(
+                        value.abs(),
+                        spec.conversion,
+                        precision.unwrap_or(6),
+                        flags.alternate,
+                    );
+                    pad_number(&mut self.out, sign, &body, width, &flags);
+                } else {
+                    let body = if value.is_nan() { "nan" } else { "inf" };
+                    let body = if spec.conversion.is_ascii_uppercase() {
+                        body.to_ascii_uppercase()
+                    } else {
+                        body.to_string()
+                    };
+                    flags.zero = false;
+                    pad_number(&mut self.out, sign, &body, width, &flags);
+                }
+            }
+            'c' => {
+                let arg = self.next_arg().unwrap_or_default();
+                let c = arg.chars().next().map(String::from).unwrap_or_default();
+                pad(&mut self.out, c.as_bytes(), width, flags.left);
+            }
+            'b' => {
+                let arg = self.next_arg().unwrap_or_default();
+                return unescape(arg, Escapes::Echo, &mut self.out);
+            }
+            'q' => {
+                let arg = self.next_arg().unwrap_or_default();
+                self.out.extend_from_slice(shell_quote(arg).as_bytes());
+            }
+            _ => {
+                let text = self.next_arg().unwrap_or_default();
+                let end = match precision {
+                    Some(p) => (0..=p.min(text.len()))
+                        .rev()
+                        .find(|&i| text.is_char_boundary(i))
+                        .unwrap_or(0),
+                    None => text.len(),
+                };
+                pad(&mut self.out, &text.as_bytes()[..end], width, flags.left);
+            }
+        }
+        true
+    }
+
+    // Prints the whole format once. Returns `false` if a `\c` stopped it.
+    fn print_format(&mut self, pieces: &[Piece]) -> bool {
+        pieces.iter().all(|piece| match piece {
+            Piece::Text(text) => unescape(text, Escapes::Format, &mut self.out),
+            Piece::Spec(spec) => self.print_spec(spec),
+        })
+    }
+
+    fn print_integer(
+        &mut self,
+        prefix: &str,
+        value: u64,
+        conversion: char,
+        width: usize,
+        precision: Option<usize>,
+        flags: &Flags,
+    ) {
+        let mut digits = match conversion {
+            'o' => format!("{:o}", value),
+            'x' => format!("{:x}", value),
+            'X' => format!("{:X}", value),
+            _ => value.to_string(),
+        };
+        if precision == Some(0) && value == 0 {
+            digits.clear();
+        }
+        if let Some(p) = precision {
+            if digits.len() < p {
+                digits.insert_str(0, &"0".repeat(p - digits.len()));
+            }
+        }
+        if conversion == 'o' && flags.alternate && !digits.starts_with('0') {
+            digits.insert(0, '0');
+        }
+
+        // A precision turns off the 0 flag for integers
+        let flags = Flags {
+            zero: flags.zero && precision.is_none(),
+            ..*flags
+        };
+        pad_number(&mut self.out, prefix, &digits, width, &flags);
+    }
+}
+
+pub fn run(config: Config) -> MyResult<bool> {
+    let pieces = parse_format(&config.format)?;
+    let mut printer = Printer {
+        args: &config.args,
+        used: 0,
+        out: vec![],
+        ok: true,
+    };
+
+    // The format is reused while it keeps consuming arguments
+    loop {
+        let used_before = printer.used;
+        if !printer.print_format(&pieces) {
+            break;
+        }
+        if printer.used == 0 && !config.args.is_empty() {
+            eprintln!(
+                "warning: ignoring excess arguments, starting with '{}'",
+                config.args[0]
+            );
+        }
+        if printer.used == used_before || printer.used == config.args.len() {
+            break;
+        }
+    }
+
+    io::stdout().write_all(&printer.out)?;
+    Ok(printer.ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_float, parse_float, parse_format, parse_integer, to_signed, to_unsigned, Count,
+        Flags, Piece, Spec, NOT_CONVERTED, NOT_NUMERIC, OUT_OF_RANGE,
+    };
+
+    #[test]
+    fn test_parse_format() {
+        let res = parse_format("n=%-08.*d%%\\n");
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap(),
+            vec![
+                Piece::Text("n=".to_string()),
+                Piece::Spec(Spec {
+                    flags: Flags {
+                        left: true,
+                        zero: true,
+                        ..Flags::default()
+                    },
+                    width: Some(Count::Fixed(8)),
+                    precision: Some(Count::FromArg),
+                    conversion: 'd',
+                }),
+                Piece::Text("%\\n".to_string()),
+            ]
+        );
+
+        // An escaped percent sign does not start a directive
+        let res = parse_format("\\%d");
+        assert_eq!(res.unwrap(), vec![Piece::Text("\\%d".to_string())]);
+
+        // Unknown conversions are errors
+        let res = parse_format("%5k");
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "%5k: invalid conversion specification"
+        );
+    }
+
+    #[test]
+    fn test_parse_integer() {
+        let value = |arg| {
+            let (int, issue) = parse_integer(arg);
+            (to_signed(&int).0, issue)
+        };
+        assert_eq!(value("42"), (42, None));
+        assert_eq!(value(" -0x1f"), (-31, None));
+        assert_eq!(value("017"), (15, None));
+        assert_eq!(value("'A"), (65, None));
+        assert_eq!(value("12abc"), (12, Some(NOT_CONVERTED)));
+        assert_eq!(value("08"), (0, Some(NOT_CONVERTED)));
+        assert_eq!(value("abc"), (0, Some(NOT_NUMERIC)));
+
+        // Values are clamped to the range of the conversion
+        let (int, _) = parse_integer("9223372036854775808");
+        assert_eq!(to_signed(&int), (i64::MAX, Some(OUT_OF_RANGE)));
+        let (int, _) = parse_integer("-9223372036854775808");
+        assert_eq!(to_signed(&int), (i64::MIN, None));
+
+        // Negative values wrap around for unsigned conversions
+        let (int, _) = parse_integer("-1");
+        assert_eq!(to_unsigned(&int), (u64::MAX, None));
+        let (int, _) = parse_integer("18446744073709551616");
+        assert_eq!(to_unsigned(&int), (u64::MAX, Some(OUT_OF_RANGE)));
+    }
+
+    #[test]
+    fn test_parse_float() {
+        assert_eq!(parse_float("2.5"), (2.5, None));
+        assert_eq!(parse_float("0x10"), (16.0, None));
+        assert_eq!(parse_float("1e"), (1.0, Some(NOT_CONVERTED)));
+        assert_eq!(parse_float("x"), (0.0, Some(NOT_NUMERIC)));
+    }
+
+    #[test]
+    fn test_format_float() {
+        assert_eq!(format_float(1.23456, 'f', 2, false), "1.23");
+        assert_eq!(format_float(3.0, 'f', 0, true), "3.");
+        assert_eq!(format_float(1234.5, 'e', 3, false), "1.234e+03");
+        assert_eq!(format_float(0.00012, 'E', 1, false), "1.2E-04");
+        assert_eq!(format_float(100000.0, 'g', 6, false), "100000");
+        assert_eq!(format_float(1000000.0, 'g', 6, false), "1e+06");
+        assert_eq!(format_float(0.0001, 'g', 6, false), "0.0001");
+        assert_eq!(format_float(0.00001, 'G', 6, false), "1E-05");
+        assert_eq!(format_float(9.9999999, 'g', 6, false), "10");
+        assert_eq!(format_float(1.0, 'g', 3, true), "1.00");
+        assert_eq!(format_float(0.0, 'g', 6, false), "0");
+    }
+}
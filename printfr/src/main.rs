@@ -0,0 +1,10 @@
+fn main() {
+    match printfr::get_args().and_then(printfr::run) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
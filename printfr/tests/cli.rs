@@ -0,0 +1,201 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const PRG: &str = "printfr";
+
+// --------------------------------------------------
+fn run(args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read_to_string(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("USAGE"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_conversion() -> TestResult {
+    for (format, expected) in [("%z", "%z"), ("x%", "%"), ("%5b", "%5b"), ("%#d", "%#d")] {
+        Command::cargo_bin(PRG)?
+            .args([format, "1"])
+            .assert()
+            .failure()
+            .stdout("")
+            .stderr(format!("{}: invalid conversion specification\n", expected));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reuses_format() -> TestResult {
+    run(&["%s-%s\\n", "a", "b", "c"], "tests/expected/reuse.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn excess_arguments() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["hello\\n", "a", "b"])
+        .assert()
+        .success()
+        .stdout("hello\n")
+        .stderr("warning: ignoring excess arguments, starting with 'a'\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn integers() -> TestResult {
+    run(
+        &[
+            "%d|%i|%u|%o|%x|%#X|%#o|%+d|% d|%05d|%-5d|%.3d|%.0d|\\n",
+            "42",
+            "0x1F",
+            "-1",
+            "8",
+            "255",
+            "255",
+            "8",
+            "5",
+            "5",
+            "-42",
+            "7",
+            "7",
+            "0",
+        ],
+        "tests/expected/integers.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn floats() -> TestResult {
+    run(
+        &[
+            "%5.2f|%-10.3e|%g|%g|%G|%#g|%010.3f|%+.1E|\\n",
+            "3.14159",
+            "1234.5",
+            "0.0001",
+            "1000000",
+            "1e-5",
+            "1",
+            "-3.14159",
+            "12345",
+        ],
+        "tests/expected/floats.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn star_width_and_precision() -> TestResult {
+    run(
+        &[
+            "%*d|%-*.*f|%.*s|\\n",
+            "6",
+            "42",
+            "8",
+            "2",
+            "3.14159",
+            "2",
+            "abc",
+        ],
+        "tests/expected/star.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn strings() -> TestResult {
+    run(
+        &[
+            "%c|%5c|%-3c|%.3s|%10s|%-10s|%%\\n",
+            "hello",
+            "x",
+            "y",
+            "abcdef",
+            "ab",
+            "ab",
+        ],
+        "tests/expected/strings.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn char_constants() -> TestResult {
+    run(&["%d %d\\n", "'A", "\"B"], "tests/expected/chars.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn format_escapes() -> TestResult {
+    run(&["\\0101\\101\\x41☺\\\"\\n"], "tests/expected/escapes.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn b_escapes() -> TestResult {
+    run(&["%b|%s\\n", "a\\tb\\0101", "\\t"], "tests/expected/b.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn b_stops_output() -> TestResult {
+    run(
+        &["%b|%s\\n", "stop\\cnow", "more"],
+        "tests/expected/b.c.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn shell_quote() -> TestResult {
+    run(
+        &["%q\\n", "a b", "it's", "", "plain", "tab\tx"],
+        "tests/expected/quote.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn negative_argument() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["%d %s\\n", "-5", "-n"])
+        .assert()
+        .success()
+        .stdout("-5 -n\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bad_numbers_keep_going() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/bad-numbers.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["%d|%d|%d\\n", "abc", "12abc", "7"])
+        .assert()
+        .failure()
+        .stdout(expected)
+        .stderr(
+            "'abc': expected a numeric value\n\
+             '12abc': value not completely converted\n",
+        );
+    Ok(())
+}
@@ -1,13 +1,32 @@
 use std::{
-    cmp::{max},
-    fs::{metadata, File},
-    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    cmp::{max, min},
+    collections::VecDeque,
+    fs::{metadata, File, Metadata},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    thread,
+    time::Duration,
 };
 
+#[cfg(unix)]
+use std::os::unix::{fs::MetadataExt, io::AsFd};
+#[cfg(windows)]
+use std::os::windows::io::AsHandle;
+
+#[cfg(target_os = "linux")]
+use std::{
+    path::Path,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+};
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime};
 use clap::{App, Arg};
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
 
 use crate::TakeValue::*;
 
+static NEWLINE: Lazy<Regex> = Lazy::new(|| Regex::new("\n").unwrap());
+
 type MyResult<T> = Result<T, Box<dyn std::error::Error>>;
 
 #[derive(Debug)]
@@ -15,27 +34,257 @@ pub struct Config {
     files: Vec<String>,
     lines: TakeValue,
     bytes: Option<TakeValue>,
+    ranges: Vec<LineRange>,
     quiet: bool,
+    follow: Option<Follow>,
+    retry: bool,
+    sleep_interval: Duration,
+    use_inotify: bool,
+    // Only read on Unix; elsewhere get_args rejects --pid
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pid: Option<u32>,
+    reverse: bool,
+    times: Option<TimeFilter>,
+}
+
+/// Options for `tacr`, which prints whole files record by record in reverse.
+#[derive(Debug)]
+pub struct TacConfig {
+    files: Vec<String>,
+    separator: Regex,
+    before: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Follow {
+    // Keep reading the file that was opened, wherever it is moved to
+    Descriptor,
+    // Keep reading whichever file has the name, reopening it if it changes
+    Name,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-enum TakeValue {
+pub enum TakeValue {
     PlusZero,
     TakeNum(i64),
 }
 
+/// Why a count passed to [`parse_size`] was rejected.
+#[derive(Debug, PartialEq)]
+pub enum SizeError {
+    Invalid,
+    TooLarge,
+}
+
+impl SizeError {
+    /// Describes the error for the count as it was given on the command line.
+    pub fn describe(&self, value: &str) -> String {
+        match self {
+            SizeError::Invalid => value.to_string(),
+            SizeError::TooLarge => format!("{}: value too large", value),
+        }
+    }
+}
+
+/// Parses an unsigned count with an optional GNU-style multiplier suffix:
+/// `b` (512), `K`/`k`, `M`/`m`, `G`, `T`, `P` or `E` for powers of 1024.
+/// The letters may be followed by `iB` (also powers of 1024) or `B` (powers
+/// of 1000), so `10KiB` is 10240 and `10KB` is 10000.
+pub fn parse_size(value: &str) -> Result<u64, SizeError> {
+    let digits_end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    if digits_end == 0 {
+        return Err(SizeError::Invalid);
+    }
+
+    let (digits, suffix) = value.split_at(digits_end);
+    let multiplier = match suffix {
+        "" => 1,
+        "b" => 512,
+        _ => {
+            let mut chars = suffix.chars();
+            let exponent = match chars.next() {
+                Some('k' | 'K') => 1,
+                Some('m' | 'M') => 2,
+                Some('G') => 3,
+                Some('T') => 4,
+                Some('P') => 5,
+                Some('E') => 6,
+                _ => return Err(SizeError::Invalid),
+            };
+            let base: u64 = match chars.as_str() {
+                "" | "iB" => 1024,
+                "B" => 1000,
+                _ => return Err(SizeError::Invalid),
+            };
+            base.pow(exponent)
+        }
+    };
+
+    digits
+        .parse::<u64>()
+        .map_err(|_| SizeError::TooLarge)?
+        .checked_mul(multiplier)
+        .ok_or(SizeError::TooLarge)
+}
+
 fn parse_num(value: &str) -> MyResult<TakeValue> {
     if value == "+0" {
         return Ok(PlusZero);
     }
-    if value.starts_with('+') {
-        Ok(TakeNum(value.parse().map_err(|_| value)?))
-    } else {
-        let value = value.parse().map_err(|_| value)?;
-        Ok(TakeNum(if value <= 0 { value } else { -value }))
+    let take_num = match value.strip_prefix('+') {
+        Some(num) => {
+            parse_size(num).and_then(|num| i64::try_from(num).map_err(|_| SizeError::TooLarge))
+        }
+        None => {
+            let num = value.strip_prefix('-').unwrap_or(value);
+            parse_size(num).and_then(|num| {
+                // The magnitude of i64::MIN is one more than i64::MAX
+                0i64.checked_sub_unsigned(num).ok_or(SizeError::TooLarge)
+            })
+        }
+    };
+    take_num
+        .map(TakeNum)
+        .map_err(|e| From::from(e.describe(value)))
+}
+
+/// A `START:END` range of lines, counted from one and including both ends.
+/// Either end may be left open, and negative values count from the end of
+/// the input, so `-10:` is the last ten lines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineRange {
+    start: Option<TakeValue>,
+    end: Option<TakeValue>,
+}
+
+impl LineRange {
+    /// Whether the total number of lines is needed to resolve the range.
+    pub fn needs_total(&self) -> bool {
+        [self.start, self.end]
+            .iter()
+            .any(|v| matches!(v, Some(TakeNum(n)) if *n < 0))
+    }
+
+    /// Returns the zero-based, half-open span of lines to print out of
+    /// `total` lines.
+    fn span(&self, total: i64) -> (u64, u64) {
+        let start = match self.start {
+            None => 0,
+            Some(val) => get_start_index(val, total).unwrap_or(total as u64),
+        };
+        let end = match self.end {
+            None | Some(PlusZero) => total,
+            Some(TakeNum(val)) if val > 0 => min(val, total),
+            Some(TakeNum(val)) => max(total.saturating_add(val + 1), 0),
+        };
+        (start, max(end as u64, start))
+    }
+}
+
+/// Parses a `START:END` line range.
+pub fn parse_range(value: &str) -> MyResult<LineRange> {
+    let parse_end = |end: &str| -> Result<Option<TakeValue>, SizeError> {
+        if end.is_empty() {
+            return Ok(None);
+        }
+        let (negative, num) = match end.strip_prefix('-') {
+            Some(num) => (true, num),
+            None => (false, end.strip_prefix('+').unwrap_or(end)),
+        };
+        match parse_size(num)? {
+            // Lines are counted from one
+            0 => Err(SizeError::Invalid),
+            num => {
+                let num = i64::try_from(num).map_err(|_| SizeError::TooLarge)?;
+                Ok(Some(TakeNum(if negative { -num } else { num })))
+            }
+        }
+    };
+
+    let (start, end) = value.split_once(':').ok_or(value)?;
+    let range = parse_end(start)
+        .and_then(|start| {
+            Ok(LineRange {
+                start,
+                end: parse_end(end)?,
+            })
+        })
+        .map_err(|e| e.describe(value))?;
+    Ok(range)
+}
+
+// The leading timestamp formats recognised when no format is given
+const ISO_8601_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+const SYSLOG_FORMAT: &str = "%b %e %H:%M:%S";
+
+// Timestamps are only looked for this far into a line
+const MAX_TIMESTAMP_LEN: usize = 128;
+
+/// Selects the lines of a log stamped from `since` to `until`, both
+/// inclusive. Each line is stamped with the time at its start, or with the
+/// time of the line before if it has none.
+#[derive(Debug)]
+struct TimeFilter {
+    since: Option<NaiveDateTime>,
+    until: Option<NaiveDateTime>,
+    // A chrono format, or None to detect ISO 8601 and syslog timestamps
+    format: Option<String>,
+    // The year of timestamps that leave it out, as syslog does
+    year: i32,
+}
+
+impl TimeFilter {
+    /// Reads the timestamp at the start of `line`. Detected timestamps may be
+    /// in square brackets.
+    fn parse(&self, line: &[u8]) -> Option<NaiveDateTime> {
+        let prefix = &line[..min(line.len(), MAX_TIMESTAMP_LEN)];
+        let text = match std::str::from_utf8(prefix) {
+            Ok(text) => text,
+            Err(e) => std::str::from_utf8(&prefix[..e.valid_up_to()]).unwrap(),
+        };
+        match &self.format {
+            Some(format) => self.parse_with(text, format),
+            None => {
+                let text = text.strip_prefix('[').unwrap_or(text);
+                ISO_8601_FORMATS
+                    .iter()
+                    .chain([&SYSLOG_FORMAT])
+                    .find_map(|format| self.parse_with(text, format))
+            }
+        }
+    }
+
+    fn parse_with(&self, text: &str, format: &str) -> Option<NaiveDateTime> {
+        if let Ok((time, _)) = NaiveDateTime::parse_and_remainder(text, format) {
+            return Some(time);
+        }
+        let text = format!("{} {}", self.year, text);
+        NaiveDateTime::parse_and_remainder(&text, &format!("%Y {}", format))
+            .ok()
+            .map(|(time, _)| time)
     }
 }
 
+/// Parses a `--since` or `--until` time: an ISO 8601 date, optionally
+/// followed by a time to the minute, second or fraction of a second.
+fn parse_time(value: &str) -> Option<NaiveDateTime> {
+    ISO_8601_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })
+}
+
 pub fn get_args() -> MyResult<Config> {
     let matches = App::new("tailr")
         .version("0.1.0")
@@ -44,8 +293,8 @@ pub fn get_args() -> MyResult<Config> {
         .arg(
             Arg::with_name("files")
                 .value_name("FILE")
-                .required(true)
                 .multiple(true)
+                .default_value("-")
                 .help("Input file(s)"),
         )
         .arg(
@@ -66,37 +315,226 @@ pub fn get_args() -> MyResult<Config> {
                 .conflicts_with("lines")
                 .help("Number of bytes"),
         )
+        .arg(
+            Arg::with_name("ranges")
+                .value_name("START:END")
+                .long("range")
+                .allow_hyphen_values(true)
+                .multiple(true)
+                .number_of_values(1)
+                .conflicts_with_all(&["lines", "bytes"])
+                .help("Lines START to END, may be repeated"),
+        )
         .arg(
             Arg::with_name("quiet")
                 .short("q")
                 .long("quiet")
                 .help("Suppress headers"),
         )
+        .arg(
+            Arg::with_name("follow")
+                .short("f")
+                .long("follow")
+                .help("Output appended data as the file grows"),
+        )
+        .arg(
+            Arg::with_name("follow_name")
+                .short("F")
+                .help("Follow the file by name and retry, so that rotation is handled")
+                .overrides_with("follow"),
+        )
+        .arg(
+            Arg::with_name("retry")
+                .long("retry")
+                .help("Keep trying to open a file if it is inaccessible"),
+        )
+        .arg(
+            Arg::with_name("sleep_interval")
+                .value_name("N")
+                .short("s")
+                .long("sleep-interval")
+                .help("With -f, check for changes at least every N seconds [default: 1.0]"),
+        )
+        .arg(
+            Arg::with_name("disable_inotify")
+                .long("disable-inotify")
+                .help("With -f, poll for changes instead of using inotify"),
+        )
+        .arg(
+            Arg::with_name("pid")
+                .value_name("PID")
+                .long("pid")
+                .help("Follow until process PID has exited (Unix only)"),
+        )
+        .arg(
+            Arg::with_name("reverse")
+                .short("r")
+                .help("Print lines in reverse order, all of them unless -n is given")
+                .conflicts_with_all(&["bytes", "ranges", "follow", "follow_name", "pid"]),
+        )
+        .arg(
+            Arg::with_name("since")
+                .value_name("TIME")
+                .long("since")
+                .conflicts_with_all(&["lines", "bytes", "ranges", "reverse"])
+                .help("Start at the first line stamped at or after TIME"),
+        )
+        .arg(
+            Arg::with_name("until")
+                .value_name("TIME")
+                .long("until")
+                .conflicts_with_all(&[
+                    "lines",
+                    "bytes",
+                    "ranges",
+                    "reverse",
+                    "follow",
+                    "follow_name",
+                    "pid",
+                ])
+                .help("Stop after the last line stamped at or before TIME"),
+        )
+        .arg(
+            Arg::with_name("time_format")
+                .value_name("FORMAT")
+                .long("time-format")
+                .help(
+                    "Read line timestamps with this strftime FORMAT [default: ISO 8601 or syslog]",
+                ),
+        )
         .get_matches();
 
-    let lines = matches
-        .value_of("lines")
-        .map(parse_num)
-        .transpose()
-        .map_err(|e| format!("illegal line count -- {}", e))?;
+    let reverse = matches.is_present("reverse");
+    let lines = if reverse && matches.occurrences_of("lines") == 0 {
+        Some(PlusZero)
+    } else {
+        matches
+            .value_of("lines")
+            .map(parse_num)
+            .transpose()
+            .map_err(|e| format!("illegal line count -- {}", e))?
+    };
     let bytes = matches
         .value_of("bytes")
         .map(parse_num)
         .transpose()
         .map_err(|e| format!("illegal byte count -- {}", e))?;
+    let ranges = matches
+        .values_of("ranges")
+        .unwrap_or_default()
+        .map(parse_range)
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("illegal line range -- {}", e))?;
+    let sleep_interval = matches
+        .value_of("sleep_interval")
+        .map(|val| {
+            val.parse()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .ok_or(val)
+        })
+        .transpose()
+        .map_err(|e| format!("invalid number of seconds -- {}", e))?;
+
+    // PIDs are positive values of the signed pid_t
+    let pid = matches
+        .value_of("pid")
+        .map(|val| match val.parse::<i32>() {
+            Ok(pid) if pid > 0 => Ok(pid as u32),
+            _ => Err(val),
+        })
+        .transpose()
+        .map_err(|e| format!("invalid PID -- {}", e))?;
+    if cfg!(not(unix)) && pid.is_some() {
+        return Err(From::from("--pid is only supported on Unix"));
+    }
+
+    let mut times = ["since", "until"].iter().map(|&name| {
+        matches
+            .value_of(name)
+            .map(|val| parse_time(val).ok_or_else(|| format!("Invalid --{} \"{}\"", name, val)))
+            .transpose()
+    });
+    let (since, until) = (times.next().unwrap()?, times.next().unwrap()?);
+    let times = (since.is_some() || until.is_some()).then(|| TimeFilter {
+        since,
+        until,
+        format: matches.value_of("time_format").map(String::from),
+        year: Local::now().year(),
+    });
+
+    let follow = if matches.is_present("follow_name") {
+        Some(Follow::Name)
+    } else if matches.is_present("follow") || pid.is_some() {
+        Some(Follow::Descriptor)
+    } else {
+        None
+    };
 
     Ok(Config {
         files: matches.values_of_lossy("files").unwrap(),
         lines: lines.unwrap(),
         bytes,
+        ranges,
         quiet: matches.is_present("quiet"),
+        follow,
+        retry: matches.is_present("retry") || matches.is_present("follow_name"),
+        sleep_interval: sleep_interval.unwrap_or(Duration::from_secs(1)),
+        use_inotify: !matches.is_present("disable_inotify"),
+        pid,
+        reverse,
+        times,
     })
 }
 
-fn count_lines_bytes(filename: &str) -> MyResult<(i64, i64)> {
-    let num_bytes = metadata(filename)?.len() as i64;
-    let num_lines = BufReader::new(File::open(filename)?).lines().count() as i64;
-    Ok((num_lines, num_bytes))
+pub fn get_tac_args() -> MyResult<TacConfig> {
+    let matches = App::new("tacr")
+        .version("0.1.0")
+        .author("Tyson Liddell <tysonliddell@hotmail.com>")
+        .about("Rust tac")
+        .arg(
+            Arg::with_name("files")
+                .value_name("FILE")
+                .multiple(true)
+                .default_value("-")
+                .help("Input file(s)"),
+        )
+        .arg(
+            Arg::with_name("before")
+                .short("b")
+                .long("before")
+                .help("Attach the separator to the start of each record"),
+        )
+        .arg(
+            Arg::with_name("regex")
+                .short("r")
+                .long("regex")
+                .help("Interpret the separator as a regular expression"),
+        )
+        .arg(
+            Arg::with_name("separator")
+                .value_name("STRING")
+                .short("s")
+                .long("separator")
+                .allow_hyphen_values(true)
+                .help("Use STRING as the separator instead of newline"),
+        )
+        .get_matches();
+
+    let separator = match matches.value_of("separator") {
+        None => NEWLINE.clone(),
+        Some("") => return Err(From::from("separator cannot be empty")),
+        Some(sep) if matches.is_present("regex") => {
+            Regex::new(sep).map_err(|_| format!("Invalid separator \"{}\"", sep))?
+        }
+        Some(sep) => Regex::new(&regex::escape(sep))?,
+    };
+
+    Ok(TacConfig {
+        files: matches.values_of_lossy("files").unwrap(),
+        separator,
+        before: matches.is_present("before"),
+    })
 }
 
 fn get_start_index(take_val: TakeValue, total: i64) -> Option<u64> {
@@ -108,82 +546,847 @@ fn get_start_index(take_val: TakeValue, total: i64) -> Option<u64> {
     start.filter(|v| total > 0 && *v < total as u64)
 }
 
-fn print_lines(mut file: impl BufRead, num_lines: TakeValue, total_lines: i64) -> MyResult<()> {
-    if let Some(mut offset) = get_start_index(num_lines, total_lines) {
-        let mut buf = String::new();
-        while offset > 0 {
-            file.read_line(&mut buf)?;
-            offset -= 1;
+// How much of the file is read at a time when scanning back from the end
+const BLOCK_SIZE: usize = 64 * 1024;
+
+// Finds where the last `num_lines` lines of `file` start by reading blocks
+// backwards from the end, so only the lines wanted are ever read.
+fn find_tail_start(file: &mut (impl Read + Seek), num_lines: u64) -> io::Result<u64> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let mut remaining = num_lines;
+    let mut buf = vec![0; BLOCK_SIZE];
+    let mut end = len;
+    while end > 0 && remaining > 0 {
+        let start = end.saturating_sub(BLOCK_SIZE as u64);
+        let block = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(block)?;
+
+        for (i, _) in block.iter().enumerate().rev().filter(|(_, b)| **b == b'\n') {
+            let line_start = start + i as u64 + 1;
+            // The newline ending the last line does not start another line
+            if line_start == len {
+                continue;
+            }
+            remaining -= 1;
+            if remaining == 0 {
+                return Ok(line_start);
+            }
         }
+        end = start;
+    }
+    Ok(0)
+}
 
-        buf.clear();
-        loop {
-            file.read_line(&mut buf)?;
-            if buf.is_empty() {
-                break;
+fn print_lines(file: &mut (impl BufRead + Seek), num_lines: TakeValue) -> MyResult<()> {
+    match num_lines {
+        TakeNum(val) if val < 0 => {
+            let start = find_tail_start(file, val.unsigned_abs())?;
+            file.seek(SeekFrom::Start(start))?;
+            io::copy(file, &mut io::stdout())?;
+            Ok(())
+        }
+        _ => print_lines_stream(file, num_lines),
+    }
+}
+
+// Finds where the lines selected by `num_lines` start in a seekable input
+fn find_lines_start(file: &mut (impl BufRead + Seek), num_lines: TakeValue) -> io::Result<u64> {
+    match num_lines {
+        PlusZero => Ok(0),
+        TakeNum(0) => file.seek(SeekFrom::End(0)),
+        TakeNum(val) if val > 0 => {
+            file.seek(SeekFrom::Start(0))?;
+            let mut offset = 0;
+            let mut buf = Vec::new();
+            for _ in 1..val {
+                buf.clear();
+                match file.read_until(b'\n', &mut buf)? {
+                    0 => break,
+                    num_bytes => offset += num_bytes as u64,
+                }
             }
+            Ok(offset)
+        }
+        TakeNum(val) => find_tail_start(file, val.unsigned_abs()),
+    }
+}
 
-            print!("{}", buf);
-            buf.clear();
+fn print_lines_reversed(file: &mut (impl BufRead + Seek), num_lines: TakeValue) -> MyResult<()> {
+    let start = find_lines_start(file, num_lines)?;
+    let end = file.seek(SeekFrom::End(0))?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    print_reversed(file, start, end, &NEWLINE, false, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the records between byte offsets `start` and `end` of `file` to
+/// `out` last to first. Records end with a match of `separator`, or start
+/// with one if `before` is set. The input is read backwards a block at a
+/// time, so only the records being split need to be held in memory. Each
+/// block is searched together with at most a block of what follows it, so
+/// a separator match longer than a block may be missed.
+pub fn print_reversed(
+    file: &mut (impl Read + Seek),
+    start: u64,
+    end: u64,
+    separator: &Regex,
+    before: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    // The record not yet printed is `head` followed by the blocks in `tail`,
+    // which are kept last to first. Only `head` is searched again.
+    let mut head = Vec::new();
+    let mut tail: Vec<Vec<u8>> = Vec::new();
+    let mut pos = end;
+    while pos > start {
+        let block_start = max(pos.saturating_sub(BLOCK_SIZE as u64), start);
+        let mut buf = vec![0; (pos - block_start) as usize];
+        file.seek(SeekFrom::Start(block_start))?;
+        file.read_exact(&mut buf)?;
+        buf.append(&mut head);
+        pos = block_start;
+
+        // The first match could still grow into the data before the buffer,
+        // so it is only trusted once that has been read
+        let mut matches = separator
+            .find_iter(&buf)
+            .filter(|m| !m.as_bytes().is_empty());
+        let first_end = if pos > start {
+            matches.next().map_or(0, |m| m.end())
+        } else {
+            0
+        };
+        let boundaries: Vec<_> = matches
+            .map(|m| if before { m.start() } else { m.end() })
+            .collect();
+
+        if let Some(&last) = boundaries.last() {
+            out.write_all(&buf[last..])?;
+            for block in tail.drain(..).rev() {
+                out.write_all(&block)?;
+            }
+            for pair in boundaries.windows(2).rev() {
+                out.write_all(&buf[pair[0]..pair[1]])?;
+            }
+            buf.truncate(boundaries[0]);
+        }
+
+        // Keep enough to find a separator that starts in the next block
+        let keep = min(buf.len(), max(first_end, BLOCK_SIZE));
+        if keep < buf.len() {
+            tail.push(buf.split_off(keep));
         }
+        head = buf;
+    }
+    out.write_all(&head)?;
+    for block in tail.iter().rev() {
+        out.write_all(block)?;
     }
     Ok(())
 }
 
+/// Copies an input that cannot be seeked into a temporary file that can,
+/// positioned at its start.
+pub fn spool(mut file: impl Read) -> io::Result<File> {
+    let mut spooled = tempfile::tempfile()?;
+    io::copy(&mut file, &mut spooled)?;
+    spooled.rewind()?;
+    Ok(spooled)
+}
+
+// Prints lines from an input that can only be read forwards, holding the
+// last lines in a ring buffer when counting from the end.
+fn print_lines_stream(mut file: impl BufRead, num_lines: TakeValue) -> MyResult<()> {
+    let mut buf = Vec::new();
+    match num_lines {
+        PlusZero => {}
+        TakeNum(0) => return Ok(()),
+        // Lines counted from the start are skipped as the file is streamed
+        TakeNum(val) if val > 0 => {
+            for _ in 1..val {
+                buf.clear();
+                if file.read_until(b'\n', &mut buf)? == 0 {
+                    break;
+                }
+            }
+        }
+        TakeNum(val) => {
+            let num_lines = val.unsigned_abs() as usize;
+            let mut held = VecDeque::new();
+            loop {
+                let mut line = Vec::new();
+                if file.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                if held.len() == num_lines {
+                    held.pop_front();
+                }
+                held.push_back(line);
+            }
+            let mut out = io::stdout().lock();
+            for line in held {
+                out.write_all(&line)?;
+            }
+            return Ok(());
+        }
+    }
+    io::copy(&mut file, &mut io::stdout())?;
+    Ok(())
+}
+
+// Prints the lines stamped within the times given. The log is taken to be
+// sorted, so reading stops at the first line stamped after `until`.
+fn print_lines_between(mut file: impl BufRead, times: &TimeFilter) -> MyResult<()> {
+    let mut out = io::stdout().lock();
+    let mut printing = times.since.is_none();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if file.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        if let Some(time) = times.parse(&line) {
+            if times.until.is_some_and(|until| time > until) {
+                break;
+            }
+            printing = printing || times.since.is_none_or(|since| time >= since);
+        }
+        if printing {
+            out.write_all(&line)?;
+            // With no end time the rest can be copied without reading it
+            if times.until.is_none() {
+                io::copy(&mut file, &mut out)?;
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Finds where the first line stamped at or after `since` starts in a log
+// sorted by time, bisecting on byte offsets so that only a few blocks have
+// to be read
+fn find_time_start(
+    file: &mut (impl BufRead + Seek),
+    times: &TimeFilter,
+    since: NaiveDateTime,
+) -> io::Result<u64> {
+    let len = file.seek(SeekFrom::End(0))?;
+    // The line wanted starts somewhere from `lo` to `hi`, and no stamped line
+    // starts from `probe_hi` up to `hi`
+    let (mut lo, mut hi, mut probe_hi) = (0, len, len);
+    let mut line = Vec::new();
+    while probe_hi.saturating_sub(lo) > BLOCK_SIZE as u64 {
+        let mid = lo + (probe_hi - lo) / 2;
+        // Skip to the start of the first line after `mid`
+        file.seek(SeekFrom::Start(mid - 1))?;
+        let mut pos = mid - 1 + file.read_until(b'\n', &mut line)? as u64;
+        let mut stamped = None;
+        while pos < hi {
+            line.clear();
+            let num_bytes = file.read_until(b'\n', &mut line)? as u64;
+            if let Some(time) = times.parse(&line) {
+                stamped = Some((pos, pos + num_bytes, time));
+                break;
+            }
+            pos += num_bytes;
+        }
+        line.clear();
+        match stamped {
+            Some((_, end, time)) if time < since => lo = end,
+            Some((start, _, _)) => {
+                hi = start;
+                probe_hi = mid;
+            }
+            None => probe_hi = mid,
+        }
+    }
+
+    file.seek(SeekFrom::Start(lo))?;
+    let mut pos = lo;
+    while pos < hi {
+        line.clear();
+        let num_bytes = file.read_until(b'\n', &mut line)? as u64;
+        if times.parse(&line).is_some_and(|time| time >= since) {
+            return Ok(pos);
+        }
+        pos += num_bytes;
+    }
+    Ok(hi)
+}
+
 fn print_bytes<T>(mut file: T, num_bytes: TakeValue, total_bytes: i64) -> MyResult<()>
 where
     T: Read + Seek,
 {
     if let Some(offset) = get_start_index(num_bytes, total_bytes) {
         file.seek(SeekFrom::Start(offset))?;
-        // let mut buf = [0u8; 1024];
-        // loop {
-        //     let n = file.read(&mut buf)?;
-        //     if n == 0 {
-        //         break;
-        //     }
-        //     std::io::stdout().write_all(&buf[..n])?;
-        // }
-        let mut buf = vec![];
-        file.read_to_end(&mut buf)?;
-        print!("{}", String::from_utf8_lossy(&buf));
+        copy_blocks(&mut file)?;
     }
     Ok(())
 }
 
-pub fn run(config: Config) -> MyResult<()> {
-    let print_header = |filename: &str, first: bool| {
-        if !first {
-            println!();
+// Copies the rest of `file` to stdout a block at a time, byte for byte
+fn copy_blocks(file: &mut impl Read) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    let mut buf = vec![0; BLOCK_SIZE];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(num_bytes) => out.write_all(&buf[..num_bytes])?,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Prints bytes from an input that can only be read forwards, holding the
+// last bytes in a ring buffer when counting from the end.
+fn print_bytes_stream(mut file: impl BufRead, num_bytes: TakeValue) -> MyResult<()> {
+    match num_bytes {
+        PlusZero => copy_blocks(&mut file)?,
+        TakeNum(0) => {}
+        TakeNum(val) if val > 0 => {
+            io::copy(&mut file.by_ref().take(val as u64 - 1), &mut io::sink())?;
+            copy_blocks(&mut file)?;
+        }
+        TakeNum(val) => {
+            let num_bytes = val.unsigned_abs() as usize;
+            let mut held = VecDeque::new();
+            loop {
+                let chunk = file.fill_buf()?;
+                if chunk.is_empty() {
+                    break;
+                }
+                held.extend(chunk);
+                let len = chunk.len();
+                file.consume(len);
+                if held.len() > num_bytes {
+                    held.drain(..held.len() - num_bytes);
+                }
+            }
+            let (front, back) = held.as_slices();
+            let mut out = io::stdout().lock();
+            out.write_all(front)?;
+            out.write_all(back)?;
+        }
+    }
+    Ok(())
+}
+
+// Every this many lines, a line's byte offset is kept in a `LineIndex`
+const INDEX_STRIDE: u64 = 1024;
+
+/// A sparse index of where lines start in a seekable input. It is filled in
+/// as the input is scanned, so later lookups can seek close to the line they
+/// need rather than reading from the start again.
+#[derive(Debug)]
+pub struct LineIndex {
+    // The byte offset of every `INDEX_STRIDE`th line, starting with line 0
+    checkpoints: Vec<u64>,
+    total_lines: Option<u64>,
+}
+
+impl Default for LineIndex {
+    fn default() -> Self {
+        LineIndex {
+            checkpoints: vec![0],
+            total_lines: None,
+        }
+    }
+}
+
+impl LineIndex {
+    /// Positions `file` at the start of the zero-based line `line`. Returns
+    /// `false` if the input has no such line.
+    pub fn seek_line(&mut self, file: &mut (impl BufRead + Seek), line: u64) -> io::Result<bool> {
+        if matches!(self.total_lines, Some(total) if line >= total) {
+            return Ok(false);
+        }
+
+        let checkpoint = min(line / INDEX_STRIDE, self.checkpoints.len() as u64 - 1);
+        let mut current = checkpoint * INDEX_STRIDE;
+        let mut offset = self.checkpoints[checkpoint as usize];
+        file.seek(SeekFrom::Start(offset))?;
+
+        let mut buf = Vec::new();
+        while current < line {
+            buf.clear();
+            let num_bytes = file.read_until(b'\n', &mut buf)?;
+            if num_bytes == 0 {
+                break;
+            }
+            current += 1;
+            offset += num_bytes as u64;
+            if current == self.checkpoints.len() as u64 * INDEX_STRIDE {
+                self.checkpoints.push(offset);
+            }
+        }
+
+        if file.fill_buf()?.is_empty() {
+            self.total_lines = Some(current);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Returns the number of lines in `file`, scanning only the part of it
+    /// that has not been indexed yet.
+    pub fn total_lines(&mut self, file: &mut (impl BufRead + Seek)) -> io::Result<u64> {
+        if self.total_lines.is_none() {
+            self.seek_line(file, u64::MAX)?;
+        }
+        Ok(self.total_lines.unwrap_or_default())
+    }
+}
+
+/// Whether `ranges` can be printed in one pass over input that cannot seek:
+/// none counts from the end, and each starts after the one before it ends.
+pub fn can_stream_ranges(ranges: &[LineRange]) -> bool {
+    !ranges.iter().any(LineRange::needs_total)
+        && ranges
+            .windows(2)
+            .all(|pair| pair[1].span(i64::MAX).0 >= pair[0].span(i64::MAX).1)
+}
+
+/// Prints `ranges` of lines from `file` as it is read, stopping once the
+/// last range has been printed. See [`can_stream_ranges`].
+pub fn print_ranges_stream(file: &mut impl BufRead, ranges: &[LineRange]) -> MyResult<()> {
+    let mut out = io::stdout().lock();
+    let mut line = Vec::new();
+    let mut current = 0;
+    for range in ranges {
+        let (start, end) = range.span(i64::MAX);
+        while current < end {
+            line.clear();
+            if file.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+            if current >= start {
+                out.write_all(&line)?;
+            }
+            current += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the lines of `range` from `file`, using and extending `index`.
+pub fn print_range(
+    file: &mut (impl BufRead + Seek),
+    index: &mut LineIndex,
+    range: LineRange,
+) -> MyResult<()> {
+    // Without negative values there is no need to find the end of the input
+    let total = if range.needs_total() {
+        index.total_lines(file)? as i64
+    } else {
+        i64::MAX
+    };
+    let (start, end) = range.span(total);
+    if start == end || !index.seek_line(file, start)? {
+        return Ok(());
+    }
+
+    let mut out = io::stdout().lock();
+    let mut line = Vec::new();
+    for _ in start..end {
+        line.clear();
+        if file.read_until(b'\n', &mut line)? == 0 {
+            break;
         }
-        println!("==> {} <==", filename);
+        out.write_all(&line)?;
+    }
+    Ok(())
+}
+
+fn print_header(filename: &str, first: bool) {
+    if !first {
+        println!();
+    }
+    let name = if filename == "-" {
+        "standard input"
+    } else {
+        filename
     };
+    println!("==> {} <==", name);
+}
+
+// Opens `filename`, with "-" meaning stdin, as a file so that regular files
+// can be seeked however they are given.
+fn open(filename: &str) -> io::Result<File> {
+    match filename {
+        #[cfg(unix)]
+        "-" => io::stdin().as_fd().try_clone_to_owned().map(File::from),
+        #[cfg(windows)]
+        "-" => io::stdin().as_handle().try_clone_to_owned().map(File::from),
+        _ => File::open(filename),
+    }
+}
+
+// Whether two sets of metadata are for the same file
+#[cfg(unix)]
+fn is_same_file(current: &Metadata, meta: &Metadata) -> bool {
+    current.dev() == meta.dev() && current.ino() == meta.ino()
+}
+
+// Without inode numbers, a file created at another time is another file
+#[cfg(not(unix))]
+fn is_same_file(current: &Metadata, meta: &Metadata) -> bool {
+    current.created().ok() == meta.created().ok()
+}
+
+// A file being followed with -f or -F
+struct Followed<'a> {
+    name: &'a str,
+    file: Option<File>,
+    // How much of `file` has been printed
+    pos: u64,
+}
+
+impl Followed<'_> {
+    // Copies whatever has been written to the file since it was last read
+    // to `out` a block at a time, calling `starting` before the first block
+    fn copy_new(&mut self, out: &mut impl Write, starting: &mut impl FnMut()) -> io::Result<()> {
+        let Some(file) = &mut self.file else {
+            return Ok(());
+        };
+        let meta = file.metadata()?;
+        if meta.is_file() && meta.len() < self.pos {
+            eprintln!("{}: file truncated", self.name);
+            self.pos = 0;
+        }
+        file.seek(SeekFrom::Start(self.pos))?;
 
+        let start = self.pos;
+        let mut buf = vec![0; BLOCK_SIZE];
+        loop {
+            match file.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(num_bytes) => {
+                    if self.pos == start {
+                        starting();
+                    }
+                    out.write_all(&buf[..num_bytes])?;
+                    self.pos += num_bytes as u64;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Reopens the file if its name now refers to a different file, as when a
+    // log is rotated. Returns whether a new file was opened.
+    fn reopen(&mut self) -> bool {
+        let current = self.file.as_ref().and_then(|file| file.metadata().ok());
+        match metadata(self.name) {
+            Ok(meta) if current.as_ref().is_some_and(|c| is_same_file(c, &meta)) => false,
+            Ok(_) => match File::open(self.name) {
+                Ok(file) => {
+                    let change = if current.is_some() {
+                        "has been replaced"
+                    } else {
+                        "has appeared"
+                    };
+                    eprintln!("{}: {}; following new file", self.name, change);
+                    self.file = Some(file);
+                    self.pos = 0;
+                    true
+                }
+                Err(e) => {
+                    if self.file.take().is_some() {
+                        eprintln!("{}: {}", self.name, e);
+                    }
+                    false
+                }
+            },
+            Err(e) => {
+                if self.file.take().is_some() {
+                    eprintln!("{}: has become inaccessible: {}", self.name, e);
+                }
+                false
+            }
+        }
+    }
+}
+
+// Waits for followed files to change, using inotify where it is available
+// and otherwise just sleeping between checks.
+enum Watcher {
+    #[cfg(target_os = "linux")]
+    Inotify {
+        watches: inotify::Watches,
+        events: Receiver<()>,
+    },
+    Poll,
+}
+
+impl Watcher {
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    fn new(use_inotify: bool) -> Self {
+        #[cfg(target_os = "linux")]
+        if let Some(mut inotify) = use_inotify
+            .then(inotify::Inotify::init)
+            .and_then(Result::ok)
+        {
+            let watches = inotify.watches();
+            let (tx, events) = mpsc::channel();
+            thread::spawn(move || {
+                let mut buf = [0; 4096];
+                while inotify.read_events_blocking(&mut buf).is_ok() {
+                    if tx.send(()).is_err() {
+                        break;
+                    }
+                }
+            });
+            return Watcher::Inotify { watches, events };
+        }
+        Watcher::Poll
+    }
+
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    fn watch(&mut self, filename: &str, by_name: bool) {
+        #[cfg(target_os = "linux")]
+        if let Watcher::Inotify { watches, .. } = self {
+            use inotify::WatchMask;
+
+            // Watches that cannot be added are covered by the timeout in wait
+            let _ = watches.add(
+                filename,
+                WatchMask::MODIFY
+                    | WatchMask::ATTRIB
+                    | WatchMask::DELETE_SELF
+                    | WatchMask::MOVE_SELF,
+            );
+            if by_name {
+                let dir = Path::new(filename)
+                    .parent()
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."));
+                let _ = watches.add(dir, WatchMask::CREATE | WatchMask::MOVED_TO);
+            }
+        }
+    }
+
+    // Returns once something may have changed, or after `timeout`
+    fn wait(&mut self, timeout: Duration) {
+        match self {
+            #[cfg(target_os = "linux")]
+            Watcher::Inotify { events, .. } => match events.recv_timeout(timeout) {
+                Ok(()) => while events.try_recv().is_ok() {},
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => *self = Watcher::Poll,
+            },
+            Watcher::Poll => thread::sleep(timeout),
+        }
+    }
+}
+
+// Whether process `pid` is still running. A zombie has exited even though it
+// can still be signalled.
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists
+    let exists = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0
+        || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+    if !exists {
+        return false;
+    }
+
+    // The state follows the parenthesised command name in /proc/PID/stat
+    match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        Ok(stat) => !stat
+            .rsplit_once(')')
+            .is_some_and(|(_, rest)| rest.trim_start().starts_with('Z')),
+        Err(_) => true,
+    }
+}
+
+fn follow(
+    config: &Config,
+    mut files: Vec<Followed>,
+    mut last_printed: Option<usize>,
+    mut printed_header: bool,
+) -> MyResult<()> {
+    let by_name = config.follow == Some(Follow::Name);
+    let show_headers = config.files.len() > 1 && !config.quiet;
+
+    let mut watcher = Watcher::new(config.use_inotify);
+    for file in files.iter().filter(|file| file.name != "-") {
+        watcher.watch(file.name, by_name);
+    }
+
+    loop {
+        if !config.retry && files.iter().all(|file| file.file.is_none()) {
+            return Err(From::from("no files remaining"));
+        }
+        watcher.wait(config.sleep_interval);
+        // Checked before reading, so that everything the process wrote is
+        // printed before stopping
+        #[cfg(unix)]
+        let exited = config.pid.is_some_and(|pid| !is_running(pid));
+        #[cfg(not(unix))]
+        let exited = false;
+
+        for (i, file) in files.iter_mut().enumerate() {
+            let name = file.name;
+            let mut starting = || {
+                if show_headers && last_printed != Some(i) {
+                    print_header(name, !printed_header);
+                    printed_header = true;
+                }
+                last_printed = Some(i);
+            };
+            let mut out = io::stdout().lock();
+            // Anything left in a rotated file comes before the new file
+            file.copy_new(&mut out, &mut starting)?;
+            // Stdin has no name to follow
+            if by_name && name != "-" && file.reopen() {
+                watcher.watch(name, by_name);
+                file.copy_new(&mut out, &mut starting)?;
+            }
+            out.flush()?;
+        }
+
+        if exited {
+            return Ok(());
+        }
+    }
+}
+
+pub fn run(config: Config) -> MyResult<()> {
+    let mut followed = vec![];
+    let mut last_printed = None;
     let mut printed_header = false;
+    let mut failed = false;
     for filename in &config.files {
-        match File::open(filename) {
-            Err(e) => eprintln!("{}: {}", filename, e),
+        match open(filename) {
+            Err(e) => {
+                eprintln!("{}: {}", filename, e);
+                failed = true;
+                if config.follow.is_some() && config.retry {
+                    followed.push(Followed {
+                        name: filename,
+                        file: None,
+                        pos: 0,
+                    });
+                }
+            }
             Ok(file) => {
-                let (total_lines, total_bytes) = count_lines_bytes(filename)?;
                 if config.files.len() > 1 && !config.quiet {
                     print_header(filename, !printed_header);
                     printed_header = true;
                 }
-                let file = BufReader::new(file);
-                if let Some(num_bytes) = config.bytes {
-                    print_bytes(file, num_bytes, total_bytes)?;
+                // Pipes, FIFOs and files such as those in /proc that report
+                // no size have to be read from start to end
+                let meta = file.metadata()?;
+                let seekable = meta.is_file() && meta.len() > 0;
+                let mut file = BufReader::new(file);
+                if !config.ranges.is_empty() {
+                    let mut index = LineIndex::default();
+                    if seekable {
+                        for range in &config.ranges {
+                            print_range(&mut file, &mut index, *range)?;
+                        }
+                    } else if can_stream_ranges(&config.ranges) {
+                        print_ranges_stream(&mut file, &config.ranges)?;
+                    } else {
+                        let mut spooled = BufReader::new(spool(&mut file)?);
+                        for range in &config.ranges {
+                            print_range(&mut spooled, &mut index, *range)?;
+                        }
+                    }
+                } else if let Some(num_bytes) = config.bytes {
+                    if seekable {
+                        print_bytes(&mut file, num_bytes, meta.len() as i64)?;
+                    } else {
+                        print_bytes_stream(&mut file, num_bytes)?;
+                    }
+                } else if let Some(times) = &config.times {
+                    if let (true, Some(since)) = (seekable, times.since) {
+                        let start = find_time_start(&mut file, times, since)?;
+                        file.seek(SeekFrom::Start(start))?;
+                    }
+                    print_lines_between(&mut file, times)?;
+                } else if config.reverse {
+                    if seekable {
+                        print_lines_reversed(&mut file, config.lines)?;
+                    } else {
+                        let mut spooled = BufReader::new(spool(&mut file)?);
+                        print_lines_reversed(&mut spooled, config.lines)?;
+                    }
+                } else if seekable {
+                    print_lines(&mut file, config.lines)?;
                 } else {
-                    print_lines(file, config.lines, total_lines)?;
+                    print_lines_stream(&mut file, config.lines)?;
+                }
+
+                // Only regular files can grow, so there is nothing to follow
+                // in anything else
+                if config.follow.is_some() && meta.is_file() {
+                    let mut file = file.into_inner();
+                    let pos = file.seek(SeekFrom::End(0))?;
+                    last_printed = Some(followed.len());
+                    followed.push(Followed {
+                        name: filename,
+                        file: Some(file),
+                        pos,
+                    });
                 }
             }
         }
     }
+
+    // Pipes are not followed, so there may be nothing left to do. Files that
+    // could not be opened are still an error, as follow reports.
+    if config.follow.is_some() && (!followed.is_empty() || failed) {
+        io::stdout().flush()?;
+        follow(&config, followed, last_printed, printed_header)?;
+    }
+    Ok(())
+}
+
+pub fn run_tac(config: TacConfig) -> MyResult<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    for filename in &config.files {
+        match open(filename) {
+            Err(e) => eprintln!("{}: {}", filename, e),
+            Ok(file) => {
+                let meta = file.metadata()?;
+                let mut file = if meta.is_file() && meta.len() > 0 {
+                    file
+                } else {
+                    spool(file)?
+                };
+                let end = file.seek(SeekFrom::End(0))?;
+                print_reversed(
+                    &mut file,
+                    0,
+                    end,
+                    &config.separator,
+                    config.before,
+                    &mut out,
+                )?;
+            }
+        }
+    }
+    out.flush()?;
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{count_lines_bytes, get_start_index, parse_num, PlusZero, TakeNum};
+    use super::{
+        can_stream_ranges, find_lines_start, find_tail_start, find_time_start, get_start_index,
+        parse_num, parse_range, parse_size, parse_time, print_reversed, LineIndex, LineRange,
+        NaiveDate, NaiveDateTime, PlusZero, Regex, SizeError, TakeNum, TimeFilter, BLOCK_SIZE,
+        INDEX_STRIDE,
+    };
+    use std::fs::File;
+    use std::io::{BufRead, Cursor};
 
     #[test]
     fn test_parse_num() {
@@ -235,17 +1438,248 @@ mod tests {
         let res = parse_num("foo");
         assert!(res.is_err());
         assert_eq!(res.unwrap_err().to_string(), "foo");
-    }
 
-    #[test]
-    fn test_count_lines_bytes() {
-        let res = count_lines_bytes("tests/inputs/one.txt");
+        // Multiplier suffixes keep the sign semantics
+        let res = parse_num("2K");
         assert!(res.is_ok());
-        assert_eq!(res.unwrap(), (1, 24));
+        assert_eq!(res.unwrap(), TakeNum(-2048));
+        let res = parse_num("+2KB");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), TakeNum(2000));
 
-        let res = count_lines_bytes("tests/inputs/ten.txt");
+        // Values that do not fit are reported as such
+        let res = parse_num(&format!("+{}", i64::MAX as u64 + 1));
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "+9223372036854775808: value too large"
+        );
+        let res = parse_num("-8E");
         assert!(res.is_ok());
-        assert_eq!(res.unwrap(), (10, 49));
+        assert_eq!(res.unwrap(), TakeNum(i64::MIN));
+        let res = parse_num("9E");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "9E: value too large");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("10"), Ok(10));
+        assert_eq!(parse_size("10b"), Ok(5120));
+        assert_eq!(parse_size("10k"), Ok(10240));
+        assert_eq!(parse_size("10K"), Ok(10240));
+        assert_eq!(parse_size("10KiB"), Ok(10240));
+        assert_eq!(parse_size("10KB"), Ok(10000));
+        assert_eq!(parse_size("1m"), Ok(1 << 20));
+        assert_eq!(parse_size("1MiB"), Ok(1 << 20));
+        assert_eq!(parse_size("1MB"), Ok(1_000_000));
+        assert_eq!(parse_size("1G"), Ok(1 << 30));
+        assert_eq!(parse_size("1GB"), Ok(1_000_000_000));
+        assert_eq!(parse_size("1T"), Ok(1 << 40));
+        assert_eq!(parse_size("1P"), Ok(1 << 50));
+        assert_eq!(parse_size("15E"), Ok(15 << 60));
+        assert_eq!(parse_size("1EB"), Ok(1_000_000_000_000_000_000));
+
+        // Suffixes must be recognised exactly
+        assert_eq!(parse_size(""), Err(SizeError::Invalid));
+        assert_eq!(parse_size("K"), Err(SizeError::Invalid));
+        assert_eq!(parse_size("+1"), Err(SizeError::Invalid));
+        assert_eq!(parse_size("1B"), Err(SizeError::Invalid));
+        assert_eq!(parse_size("1g"), Err(SizeError::Invalid));
+        assert_eq!(parse_size("1KiBB"), Err(SizeError::Invalid));
+        assert_eq!(parse_size("1Z"), Err(SizeError::Invalid));
+
+        // Overflow is detected in both the digits and the multiplication
+        assert_eq!(parse_size("16E"), Err(SizeError::TooLarge));
+        assert_eq!(parse_size("99999999999999999999"), Err(SizeError::TooLarge));
+    }
+
+    #[test]
+    fn test_find_tail_start() {
+        let mut file = File::open("tests/inputs/one.txt").unwrap();
+        assert_eq!(find_tail_start(&mut file, 1).unwrap(), 0);
+        assert_eq!(find_tail_start(&mut file, 2).unwrap(), 0);
+
+        let mut file = File::open("tests/inputs/ten.txt").unwrap();
+        assert_eq!(find_tail_start(&mut file, 10).unwrap(), 0);
+        assert_eq!(find_tail_start(&mut file, 1).unwrap(), 45);
+        assert_eq!(find_tail_start(&mut file, 3).unwrap(), 34);
+
+        // A last line without a newline is still a line
+        let mut file = Cursor::new("a\nb\nc");
+        assert_eq!(find_tail_start(&mut file, 1).unwrap(), 4);
+        assert_eq!(find_tail_start(&mut file, 3).unwrap(), 0);
+
+        // Lines may span several blocks
+        let text = format!("{}\n{}\n", "x".repeat(BLOCK_SIZE * 2), "y".repeat(10));
+        let mut file = Cursor::new(text);
+        assert_eq!(
+            find_tail_start(&mut file, 1).unwrap(),
+            BLOCK_SIZE as u64 * 2 + 1
+        );
+        assert_eq!(find_tail_start(&mut file, 2).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_find_lines_start() {
+        let mut file = Cursor::new("a\nb\nc\n");
+        assert_eq!(find_lines_start(&mut file, PlusZero).unwrap(), 0);
+        assert_eq!(find_lines_start(&mut file, TakeNum(0)).unwrap(), 6);
+        assert_eq!(find_lines_start(&mut file, TakeNum(2)).unwrap(), 2);
+        assert_eq!(find_lines_start(&mut file, TakeNum(9)).unwrap(), 6);
+        assert_eq!(find_lines_start(&mut file, TakeNum(-1)).unwrap(), 4);
+    }
+
+    fn reversed(text: &str, start: u64, separator: &str, before: bool) -> String {
+        let mut out = Vec::new();
+        let separator = Regex::new(separator).unwrap();
+        let end = text.len() as u64;
+        print_reversed(
+            &mut Cursor::new(text),
+            start,
+            end,
+            &separator,
+            before,
+            &mut out,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_print_reversed() {
+        assert_eq!(reversed("", 0, "\n", false), "");
+        assert_eq!(reversed("a\nb\nc\n", 0, "\n", false), "c\nb\na\n");
+        assert_eq!(reversed("a\nb\nc", 0, "\n", false), "cb\na\n");
+        assert_eq!(reversed("a\nb\nc\n", 2, "\n", false), "c\nb\n");
+        assert_eq!(reversed("a\nb\nc\n", 0, "\n", true), "\n\nc\nba");
+
+        // A separator match is never split between blocks
+        let dashes = "-".repeat(BLOCK_SIZE - 1);
+        let text = format!("{}a---bc", dashes);
+        assert_eq!(reversed(&text, 0, "-+", false), format!("bca---{}", dashes));
+
+        // Records may span several blocks
+        let long = "y".repeat(BLOCK_SIZE * 2);
+        let text = format!("x\n{}\nz\n", long);
+        assert_eq!(reversed(&text, 0, "\n", false), format!("z\n{}\nx\n", long));
+        let text = format!("{}\n{}", long, long);
+        assert_eq!(
+            reversed(&text, 0, "\n", true),
+            format!("\n{}{}", long, long)
+        );
+        assert_eq!(reversed(&long, 0, "\n", false), long);
+    }
+
+    fn time(value: &str) -> NaiveDateTime {
+        parse_time(value).unwrap()
+    }
+
+    fn time_filter(format: Option<&str>) -> TimeFilter {
+        TimeFilter {
+            since: None,
+            until: None,
+            format: format.map(String::from),
+            year: 2026,
+        }
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(
+            parse_time("2026-10-17"),
+            NaiveDate::from_ymd_opt(2026, 10, 17)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(
+            parse_time("2026-10-17T09:00"),
+            parse_time("2026-10-17 09:00:00")
+        );
+        assert_eq!(
+            parse_time("2026-10-17T09:00:01.5"),
+            NaiveDate::from_ymd_opt(2026, 10, 17)
+                .unwrap()
+                .and_hms_milli_opt(9, 0, 1, 500)
+        );
+        assert_eq!(parse_time("2026-10-17T09"), None);
+        assert_eq!(parse_time("09:00"), None);
+        assert_eq!(parse_time("2026-13-01"), None);
+    }
+
+    #[test]
+    fn test_time_filter_parse() {
+        let times = time_filter(None);
+        assert_eq!(
+            times.parse(b"2026-10-17T09:00:00 INFO up\n"),
+            Some(time("2026-10-17T09:00"))
+        );
+        assert_eq!(
+            times.parse(b"[2026-10-17 09:00:00.25] up"),
+            Some(time("2026-10-17T09:00:00.25"))
+        );
+        assert_eq!(
+            times.parse(b"2026-10-17T09:00+02:00 up"),
+            Some(time("2026-10-17T09:00"))
+        );
+        // Syslog leaves out the year and pads the day with a space
+        assert_eq!(
+            times.parse(b"Oct  7 09:00:00 host sshd[1]: up"),
+            Some(time("2026-10-07T09:00"))
+        );
+        assert_eq!(times.parse(b"  at main.rs:12"), None);
+        assert_eq!(times.parse(b""), None);
+        assert_eq!(times.parse(b"\xff2026-10-17T09:00"), None);
+
+        let times = time_filter(Some("%d/%b/%Y:%H:%M:%S"));
+        assert_eq!(
+            times.parse(b"17/Oct/2026:09:00:00 GET /"),
+            Some(time("2026-10-17T09:00"))
+        );
+        assert_eq!(times.parse(b"2026-10-17T09:00:00 GET /"), None);
+
+        let times = time_filter(Some("%m-%d %H:%M"));
+        assert_eq!(
+            times.parse(b"10-17 09:00 up"),
+            Some(time("2026-10-17T09:00"))
+        );
+    }
+
+    #[test]
+    fn test_find_time_start() {
+        // A log of several blocks with a line every second, and lines with
+        // no timestamp after some of them
+        let start = time("2026-10-17");
+        let mut log = String::new();
+        let mut offsets = Vec::new();
+        for secs in 0..10_000 {
+            offsets.push(log.len() as u64);
+            let stamp = start + chrono::Duration::seconds(secs);
+            log.push_str(&format!(
+                "{} event {}\n",
+                stamp.format("%Y-%m-%dT%H:%M:%S"),
+                secs
+            ));
+            if secs % 7 == 0 {
+                log.push_str("  more detail\n  and more\n");
+            }
+        }
+        assert!(log.len() > BLOCK_SIZE * 4);
+
+        let times = time_filter(None);
+        let mut file = Cursor::new(log.as_bytes());
+        for secs in [0, 1, 7, 8, 4321, 9999] {
+            let since = start + chrono::Duration::seconds(secs);
+            assert_eq!(
+                find_time_start(&mut file, &times, since).unwrap(),
+                offsets[secs as usize]
+            );
+        }
+        let since = time("2026-10-18");
+        assert_eq!(
+            find_time_start(&mut file, &times, since).unwrap(),
+            log.len() as u64
+        );
     }
 
     #[test]
@@ -282,4 +1716,125 @@ mod tests {
         // return 0 to print the whole file
         assert_eq!(get_start_index(TakeNum(-20), 10), Some(0));
     }
+
+    #[test]
+    fn test_parse_range() {
+        let res = parse_range("100:200");
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap(),
+            LineRange {
+                start: Some(TakeNum(100)),
+                end: Some(TakeNum(200)),
+            }
+        );
+
+        // Either end may be open or count from the end
+        let res = parse_range("-10:");
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap(),
+            LineRange {
+                start: Some(TakeNum(-10)),
+                end: None,
+            }
+        );
+        let res = parse_range(":+1K");
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap(),
+            LineRange {
+                start: None,
+                end: Some(TakeNum(1024)),
+            }
+        );
+
+        // Lines are counted from one and a colon is required
+        for bad in ["0:5", "5", "a:b", "1:2:3"] {
+            let res = parse_range(bad);
+            assert!(res.is_err());
+            assert_eq!(res.unwrap_err().to_string(), bad);
+        }
+        let res = parse_range("1:16E");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "1:16E: value too large");
+    }
+
+    #[test]
+    fn test_range_span() {
+        let span = |range: &str, total| parse_range(range).unwrap().span(total);
+
+        assert_eq!(span("2:4", 10), (1, 4));
+        assert_eq!(span(":", 10), (0, 10));
+        assert_eq!(span("8:20", 10), (7, 10));
+        assert_eq!(span("-3:", 10), (7, 10));
+        assert_eq!(span("-20:-9", 10), (0, 2));
+        assert_eq!(span(":-1", 10), (0, 10));
+        assert_eq!(span("5:2", 10), (4, 4));
+        assert_eq!(span("20:", 10), (10, 10));
+        assert_eq!(span("1:", 0), (0, 0));
+    }
+
+    #[test]
+    fn test_can_stream_ranges() {
+        let ranges = |values: &[&str]| -> Vec<LineRange> {
+            values.iter().map(|v| parse_range(v).unwrap()).collect()
+        };
+
+        assert!(can_stream_ranges(&ranges(&["2:4"])));
+        assert!(can_stream_ranges(&ranges(&[":3", "4:6", "10:"])));
+        assert!(!can_stream_ranges(&ranges(&["1:3", "3:"])));
+        assert!(!can_stream_ranges(&ranges(&["5:", "1:2"])));
+        assert!(!can_stream_ranges(&ranges(&["-3:"])));
+        assert!(!can_stream_ranges(&ranges(&["2:-1"])));
+    }
+
+    #[test]
+    fn test_line_index() {
+        let num_lines = 3 * INDEX_STRIDE + 5;
+        let text: String = (0..num_lines).map(|n| format!("{}\n", n)).collect();
+        let mut file = Cursor::new(text);
+        let mut index = LineIndex::default();
+
+        let mut line = String::new();
+        assert!(index.seek_line(&mut file, 2 * INDEX_STRIDE + 1).unwrap());
+        file.read_line(&mut line).unwrap();
+        assert_eq!(line, format!("{}\n", 2 * INDEX_STRIDE + 1));
+        assert_eq!(index.checkpoints.len(), 3);
+
+        // Lines before the furthest checkpoint are found from the index
+        line.clear();
+        assert!(index.seek_line(&mut file, 7).unwrap());
+        file.read_line(&mut line).unwrap();
+        assert_eq!(line, "7\n");
+
+        assert_eq!(index.total_lines(&mut file).unwrap(), num_lines);
+        assert_eq!(index.checkpoints.len(), 4);
+        assert!(!index.seek_line(&mut file, num_lines).unwrap());
+
+        // A final line without a newline still counts
+        let mut index = LineIndex::default();
+        assert_eq!(index.total_lines(&mut Cursor::new("a\nb")).unwrap(), 2);
+        let mut index = LineIndex::default();
+        assert_eq!(index.total_lines(&mut Cursor::new("")).unwrap(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_running() {
+        use super::is_running;
+
+        assert!(is_running(std::process::id()));
+
+        // An exited child counts as stopped even before it is reaped
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        let start = std::time::Instant::now();
+        while is_running(pid) {
+            assert!(start.elapsed().as_secs() < 5, "child never stopped");
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        child.wait().unwrap();
+        assert!(!is_running(pid));
+    }
 }
@@ -1,8 +1,12 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
-use std::fs::{self, File};
-use std::io::Read;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Child, Output, Stdio};
+use std::thread;
+use std::time::Duration;
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
@@ -34,13 +38,8 @@ fn gen_bad_file() -> String {
 
 // --------------------------------------------------
 #[test]
-fn dies_no_args() -> TestResult {
-    Command::cargo_bin(PRG)?
-        .assert()
-        .failure()
-        .stderr(predicate::str::contains("USAGE"));
-
-    Ok(())
+fn no_args_reads_stdin() -> TestResult {
+    run_stdin(&[], TEN, "tests/expected/ten.txt.out")
 }
 
 // --------------------------------------------------
@@ -49,7 +48,7 @@ fn dies_bad_bytes() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal byte count -- {}", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-c", &bad, EMPTY])
+        .args(["-c", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -63,7 +62,7 @@ fn dies_bad_lines() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal line count -- {}", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-n", &bad, EMPTY])
+        .args(["-n", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -71,6 +70,20 @@ fn dies_bad_lines() -> TestResult {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_too_large() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "16E", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "illegal byte count -- 16E: value too large",
+        ));
+
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn dies_bytes_and_lines() -> TestResult {
@@ -78,7 +91,7 @@ fn dies_bytes_and_lines() -> TestResult {
                used with '--bytes <BYTES>'";
 
     Command::cargo_bin(PRG)?
-        .args(&["-n", "1", "-c", "2"])
+        .args(["-n", "1", "-c", "2"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(msg));
@@ -92,7 +105,7 @@ fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&[ONE, &bad, TWO])
+        .args([ONE, &bad, TWO])
         .assert()
         .stderr(predicate::str::is_match(expected)?);
 
@@ -101,16 +114,27 @@ fn skips_bad_file() -> TestResult {
 
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
+    let expected = fs::read(expected_file)?;
 
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
-        .stdout(predicate::eq(expected.as_bytes() as &[u8]));
+        .stdout(predicate::eq(expected));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
+    let input = fs::read(input_file)?;
+
+    Command::cargo_bin(PRG)?
+        .write_stdin(input)
+        .args(args)
+        .assert()
+        .success()
+        .stdout(predicate::eq(expected));
 
     Ok(())
 }
@@ -605,6 +629,16 @@ fn ten() -> TestResult {
     run(&[TEN], "tests/expected/ten.txt.out")
 }
 
+#[test]
+fn ten_n_suffix() -> TestResult {
+    run(&[TEN, "-n", "1K"], "tests/expected/ten.txt.n1K.out")
+}
+
+#[test]
+fn ten_c_plus_suffix() -> TestResult {
+    run(&[TEN, "-c", "+1b"], "tests/expected/ten.txt.c+1b.out")
+}
+
 #[test]
 fn ten_n0() -> TestResult {
     run(&[TEN, "-n", "0"], "tests/expected/ten.txt.n0.out")
@@ -829,3 +863,619 @@ fn multiple_files_c_plus_3() -> TestResult {
         "tests/expected/all.c+3.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn ten_range() -> TestResult {
+    run(
+        &[TEN, "--range", "3:5"],
+        "tests/expected/ten.txt.range3-5.out",
+    )
+}
+
+#[test]
+fn ten_range_from_end() -> TestResult {
+    run(
+        &[TEN, "--range", "-3:"],
+        "tests/expected/ten.txt.range-3.out",
+    )
+}
+
+#[test]
+fn ten_ranges() -> TestResult {
+    run(
+        &[TEN, "--range", "8:-2", "--range", ":2"],
+        "tests/expected/ten.txt.ranges.out",
+    )
+}
+
+#[test]
+fn multiple_files_range() -> TestResult {
+    run(
+        &[TEN, EMPTY, ONE, THREE, TWO, "--range", "2:"],
+        "tests/expected/all.range2-.out",
+    )
+}
+
+#[test]
+fn dies_bad_range() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([TEN, "--range", "0:5"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("illegal line range -- 0:5"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_reverse() -> TestResult {
+    run(&["-r", TEN], "tests/expected/ten.txt.r.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_n3_reverse() -> TestResult {
+    run(&["-r", "-n", "3", TEN], "tests/expected/ten.txt.n3.r.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_n_plus_8_reverse() -> TestResult {
+    run(&["-r", "-n", "+8", TEN], "tests/expected/ten.txt.n+8.r.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn three_reverse() -> TestResult {
+    run(&["-r", THREE], "tests/expected/three.txt.r.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_n3_reverse_stdin() -> TestResult {
+    run_stdin(&["-r", "-n", "3"], TEN, "tests/expected/ten.txt.n3.r.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_reverse_bytes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-r", "-c", "3", TEN])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+// Long enough for a follower polling every 0.05 seconds to catch up
+const SETTLE: Duration = Duration::from_millis(400);
+
+fn spawn_follower(args: &[&str]) -> Result<Child, Box<dyn std::error::Error>> {
+    let child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .args(["-s", "0.05"])
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    thread::sleep(SETTLE);
+    Ok(child)
+}
+
+fn stop_follower(mut child: Child) -> Result<Output, Box<dyn std::error::Error>> {
+    thread::sleep(SETTLE);
+    child.kill()?;
+    Ok(child.wait_with_output()?)
+}
+
+fn append(path: &Path, text: &str) -> TestResult {
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)?
+        .write_all(text.as_bytes())?;
+    Ok(())
+}
+
+#[test]
+fn follow_appended() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "one\n")?;
+
+    let child = spawn_follower(&["-f", log.to_str().unwrap()])?;
+    append(&log, "two\n")?;
+    thread::sleep(SETTLE);
+    append(&log, "three\n")?;
+    let output = stop_follower(child)?;
+
+    assert_eq!(String::from_utf8(output.stdout)?, "one\ntwo\nthree\n");
+    Ok(())
+}
+
+#[test]
+fn follow_polling() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "one\n")?;
+
+    let child = spawn_follower(&["-f", "--disable-inotify", log.to_str().unwrap()])?;
+    append(&log, "two\n")?;
+    let output = stop_follower(child)?;
+
+    assert_eq!(String::from_utf8(output.stdout)?, "one\ntwo\n");
+    Ok(())
+}
+
+#[test]
+fn follow_truncated() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "a long first line\n")?;
+
+    let child = spawn_follower(&["-f", log.to_str().unwrap()])?;
+    fs::write(&log, "new\n")?;
+    let output = stop_follower(child)?;
+
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "a long first line\nnew\n"
+    );
+    assert!(String::from_utf8(output.stderr)?.contains("file truncated"));
+    Ok(())
+}
+
+#[test]
+fn follow_descriptor_after_rename() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    let rotated = dir.path().join("log.1");
+    fs::write(&log, "one\n")?;
+
+    let child = spawn_follower(&["-f", log.to_str().unwrap()])?;
+    fs::rename(&log, &rotated)?;
+    append(&rotated, "two\n")?;
+    fs::write(&log, "ignored\n")?;
+    let output = stop_follower(child)?;
+
+    assert_eq!(String::from_utf8(output.stdout)?, "one\ntwo\n");
+    Ok(())
+}
+
+#[test]
+fn follow_name_after_rotation() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    let rotated = dir.path().join("log.1");
+    fs::write(&log, "one\n")?;
+
+    let child = spawn_follower(&["-F", log.to_str().unwrap()])?;
+    append(&log, "two\n")?;
+    thread::sleep(SETTLE);
+    fs::rename(&log, &rotated)?;
+    fs::write(&log, "three\n")?;
+    let output = stop_follower(child)?;
+
+    assert_eq!(String::from_utf8(output.stdout)?, "one\ntwo\nthree\n");
+    Ok(())
+}
+
+#[test]
+fn follow_name_retries_missing() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+
+    let child = spawn_follower(&["-F", log.to_str().unwrap()])?;
+    fs::write(&log, "one\n")?;
+    let output = stop_follower(child)?;
+
+    assert_eq!(String::from_utf8(output.stdout)?, "one\n");
+    assert!(String::from_utf8(output.stderr)?.contains("has appeared"));
+    Ok(())
+}
+
+#[test]
+fn follow_headers() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+    fs::write(&a, "a1\n")?;
+    fs::write(&b, "b1\n")?;
+    let (a_name, b_name) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let child = spawn_follower(&["-f", a_name, b_name])?;
+    append(&b, "b2\n")?;
+    thread::sleep(SETTLE);
+    append(&a, "a2\n")?;
+    let output = stop_follower(child)?;
+
+    let expected = format!(
+        "==> {a} <==\na1\n\n==> {b} <==\nb1\nb2\n\n==> {a} <==\na2\n",
+        a = a_name,
+        b = b_name
+    );
+    assert_eq!(String::from_utf8(output.stdout)?, expected);
+    Ok(())
+}
+
+#[test]
+fn follow_quiet() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+    fs::write(&a, "a1\n")?;
+    fs::write(&b, "b1\n")?;
+
+    let child = spawn_follower(&["-f", "-q", a.to_str().unwrap(), b.to_str().unwrap()])?;
+    append(&a, "a2\n")?;
+    let output = stop_follower(child)?;
+
+    assert_eq!(String::from_utf8(output.stdout)?, "a1\nb1\na2\n");
+    Ok(())
+}
+
+#[test]
+fn dies_follow_no_files_remaining() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "tests/inputs/missing.txt"])
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no files remaining"));
+
+    Ok(())
+}
+
+#[test]
+fn follow_stdin_pipe() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "-n", "2"])
+        .write_stdin("a\nb\nc\n")
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout("b\nc\n");
+
+    Ok(())
+}
+
+#[test]
+fn follow_name_stdin_pipe() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("-F")
+        .write_stdin("a\n")
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout("a\n");
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_sleep_interval() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "-s", "soon", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid number of seconds -- soon",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn follow_pid_until_exit() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "one\n")?;
+
+    let script = format!(
+        "sleep 0.3; echo two >> '{log}'; sleep 0.3; echo three >> '{log}'",
+        log = log.display()
+    );
+    let mut writer = std::process::Command::new("sh")
+        .args(["-c", &script])
+        .spawn()?;
+    let pid = format!("--pid={}", writer.id());
+    let mut follower = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .args(["-s", "0.05", &pid, log.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    writer.wait()?;
+    for _ in 0..100 {
+        if follower.try_wait()?.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    if follower.try_wait()?.is_none() {
+        follower.kill()?;
+        panic!("tailr kept following after the process exited");
+    }
+
+    let output = follower.wait_with_output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "one\ntwo\nthree\n");
+    Ok(())
+}
+
+#[test]
+fn follow_pid_already_exited() -> TestResult {
+    let mut child = std::process::Command::new("true").spawn()?;
+    child.wait()?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-s", "0.05", &format!("--pid={}", child.id()), ONE])
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout(fs::read_to_string("tests/expected/one.txt.out")?);
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_pid() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--pid", "0", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid PID -- 0"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_stdin() -> TestResult {
+    run_stdin(&["-"], TEN, "tests/expected/ten.txt.out")
+}
+
+#[test]
+fn ten_n3_stdin() -> TestResult {
+    run_stdin(&["-n", "3"], TEN, "tests/expected/ten.txt.n3.out")
+}
+
+#[test]
+fn ten_n0_stdin() -> TestResult {
+    run_stdin(&["-n", "0"], TEN, "tests/expected/ten.txt.n0.out")
+}
+
+#[test]
+fn ten_n_plus_2_stdin() -> TestResult {
+    run_stdin(&["-n", "+2"], TEN, "tests/expected/ten.txt.n+2.out")
+}
+
+#[test]
+fn ten_n200_stdin() -> TestResult {
+    run_stdin(&["-n", "200"], TEN, "tests/expected/ten.txt.n200.out")
+}
+
+#[test]
+fn ten_c8_stdin() -> TestResult {
+    run_stdin(&["-c", "8"], TEN, "tests/expected/ten.txt.c8.out")
+}
+
+#[test]
+fn ten_c_plus_2_stdin() -> TestResult {
+    run_stdin(&["-c", "+2"], TEN, "tests/expected/ten.txt.c+2.out")
+}
+
+#[test]
+fn ten_range_stdin() -> TestResult {
+    run_stdin(
+        &["--range", "-3:"],
+        TEN,
+        "tests/expected/ten.txt.range-3.out",
+    )
+}
+
+#[test]
+fn file_and_stdin() -> TestResult {
+    run_stdin(
+        &["-n", "3", ONE, "-"],
+        TEN,
+        "tests/expected/one_stdin.n3.out",
+    )
+}
+
+#[test]
+fn fifo() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let fifo = dir.path().join("fifo");
+    assert!(std::process::Command::new("mkfifo")
+        .arg(&fifo)
+        .status()?
+        .success());
+
+    let writer = {
+        let fifo = fifo.clone();
+        thread::spawn(move || fs::write(fifo, fs::read(TEN)?))
+    };
+    Command::cargo_bin(PRG)?
+        .args(["-n", "3", fifo.to_str().unwrap()])
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout(fs::read_to_string("tests/expected/ten.txt.n3.out")?);
+    writer.join().unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn proc_file() -> TestResult {
+    // Files in /proc report a size of zero however much they contain
+    let expected = fs::read_to_string("/proc/self/mounts")?
+        .lines()
+        .last()
+        .map(|line| format!("{}\n", line))
+        .unwrap_or_default();
+    Command::cargo_bin(PRG)?
+        .args(["-n", "1", "/proc/self/mounts"])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+const APP_LOG: &str = "tests/inputs/app.log";
+
+#[test]
+fn since() -> TestResult {
+    run(
+        &["--since", "2026-10-17T09:00", APP_LOG],
+        "tests/expected/app.log.since.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn since_until() -> TestResult {
+    run(
+        &[
+            "--since",
+            "2026-10-17T09:00:01",
+            "--until",
+            "2026-10-17T09:30",
+            APP_LOG,
+        ],
+        "tests/expected/app.log.since.until.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn until() -> TestResult {
+    run(
+        &["--until", "2026-10-17T09:00", APP_LOG],
+        "tests/expected/app.log.until.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn since_until_stdin() -> TestResult {
+    run_stdin(
+        &[
+            "--since",
+            "2026-10-17T09:00:01",
+            "--until",
+            "2026-10-17T09:30",
+        ],
+        APP_LOG,
+        "tests/expected/app.log.since.until.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn since_time_format() -> TestResult {
+    run(
+        &[
+            "--since",
+            "2026-10-17T10:00",
+            "--time-format",
+            "[%Y-%m-%d %H:%M:%S]",
+            APP_LOG,
+        ],
+        "tests/expected/app.log.format.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn since_syslog() -> TestResult {
+    // Syslog timestamps have no year, so they are taken to be this year's
+    let since = format!("{}-10-10", chrono::Local::now().format("%Y"));
+    run(
+        &["--since", &since, "tests/inputs/syslog.log"],
+        "tests/expected/syslog.log.since.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_since() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--since", "yesterday", APP_LOG])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#"Invalid --since "yesterday""#));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn image_c100() -> TestResult {
+    run(
+        &["-c", "100", "tests/inputs/image.png"],
+        "tests/expected/image.png.c100.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn image_c_plus_9() -> TestResult {
+    run(
+        &["-c", "+9", "tests/inputs/image.png"],
+        "tests/expected/image.png.c+9.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn random_c1000() -> TestResult {
+    run(
+        &["-c", "1000", "tests/inputs/random.bin"],
+        "tests/expected/random.bin.c1000.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn random_c_plus_4000_stdin() -> TestResult {
+    run_stdin(
+        &["-c", "+4000"],
+        "tests/inputs/random.bin",
+        "tests/expected/random.bin.c+4000.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn random_c1000_stdin() -> TestResult {
+    run_stdin(
+        &["-c", "1000"],
+        "tests/inputs/random.bin",
+        "tests/expected/random.bin.c1000.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn large_random_bytes() -> TestResult {
+    // Several blocks' worth, so the output is written in more than one piece
+    let mut bytes = vec![0; 300_000];
+    rand::thread_rng().fill(&mut bytes[..]);
+    let mut file = tempfile::NamedTempFile::new()?;
+    file.write_all(&bytes)?;
+    let path = file.path().to_str().unwrap();
+
+    for (count, start) in [("250000", 50_000), ("+2", 1), ("+0", 0)] {
+        Command::cargo_bin(PRG)?
+            .args(["-c", count, path])
+            .assert()
+            .success()
+            .stdout(predicate::eq(&bytes[start..]));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const PRG: &str = "tacr";
+const EMPTY: &str = "tests/inputs/empty.txt";
+const ONE: &str = "tests/inputs/one.txt";
+const THREE: &str = "tests/inputs/three.txt";
+const TEN: &str = "tests/inputs/ten.txt";
+const BLANK_RUNS: &str = "tests/inputs/blank_runs.txt";
+
+// --------------------------------------------------
+fn run(args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(predicate::eq(expected));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn empty() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg(EMPTY)
+        .assert()
+        .success()
+        .stdout("");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ten() -> TestResult {
+    run(&[TEN], "tests/expected/tacr/ten.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .write_stdin(fs::read(TEN)?)
+        .assert()
+        .success()
+        .stdout(predicate::eq(fs::read("tests/expected/tacr/ten.txt.out")?));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_trailing_newline() -> TestResult {
+    run(&[THREE], "tests/expected/tacr/three.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn separator() -> TestResult {
+    run(&["-s", "e", TEN], "tests/expected/tacr/ten.txt.se.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn separator_before() -> TestResult {
+    run(
+        &["-b", "-s", "e", TEN],
+        "tests/expected/tacr/ten.txt.b.se.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_separator() -> TestResult {
+    run(
+        &["-r", "-s", r"\n+", BLANK_RUNS],
+        "tests/expected/tacr/blank_runs.txt.r.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files() -> TestResult {
+    run(&[ONE, TEN], "tests/expected/tacr/all.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_empty_separator() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-s", "", TEN])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("separator cannot be empty"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_regex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-r", "-s", "(", TEN])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#"Invalid separator "(""#));
+
+    Ok(())
+}
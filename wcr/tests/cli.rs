@@ -9,6 +9,8 @@ const PRG: &str = "wcr";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
+const WIDE: &str = "tests/inputs/wide.txt";
+const MIXED: &str = "tests/inputs/mixed.txt";
 
 // --------------------------------------------------
 fn gen_bad_file() -> String {
@@ -29,7 +31,7 @@ fn gen_bad_file() -> String {
 #[test]
 fn dies_chars_and_bytes() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["-m", "-c"])
+        .args(["-m", "-c"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(
@@ -162,8 +164,7 @@ fn atlamal_bytes_lines() -> TestResult {
 #[test]
 fn atlamal_stdin() -> TestResult {
     let input = fs::read_to_string(ATLAMAL)?;
-    let expected =
-        fs::read_to_string("tests/expected/atlamal.txt.stdin.out")?;
+    let expected = fs::read_to_string("tests/expected/atlamal.txt.stdin.out")?;
     Command::cargo_bin(PRG)?
         .write_stdin(input)
         .assert()
@@ -212,3 +213,197 @@ fn test_all_words_lines() -> TestResult {
 fn test_all_bytes_lines() -> TestResult {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn wide_max_line_length() -> TestResult {
+    run(&["-L", WIDE], "tests/expected/wide.txt.L.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn wide_lines_max_line_length() -> TestResult {
+    run(&["-lL", WIDE], "tests/expected/wide.txt.lL.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_max_line_length() -> TestResult {
+    run(
+        &["--max-line-length", EMPTY, FOX, ATLAMAL, WIDE],
+        "tests/expected/all.L.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mixed_encodings() -> TestResult {
+    run(&[MIXED], "tests/expected/mixed.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn mixed_encodings_chars() -> TestResult {
+    run(&["-m", MIXED], "tests/expected/mixed.txt.m.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn mixed_encodings_unicode_words() -> TestResult {
+    run(
+        &["--words=unicode", MIXED],
+        "tests/expected/mixed.txt.unicode.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mixed_encodings_all_counts() -> TestResult {
+    run(
+        &["-lmL", "--words=unicode", MIXED],
+        "tests/expected/mixed.txt.lwmL.unicode.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_word_spaces() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--words=latin1", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'latin1' isn't a valid value"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from() -> TestResult {
+    run(
+        &["--files0-from", "tests/inputs/files0.txt"],
+        "tests/expected/files0.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin() -> TestResult {
+    let input = fs::read("tests/inputs/files0.txt")?;
+    let expected = fs::read_to_string("tests/expected/files0.txt.stdin.out")?;
+    Command::cargo_bin(PRG)?
+        .arg("--files0-from=-")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_skips_bad_names() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/files0_bad.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .arg("--files0-from=tests/inputs/files0_bad.txt")
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr(predicate::str::contains(
+            "tests/inputs/files0_bad.txt:2: invalid zero-length file name",
+        ))
+        .stderr(predicate::str::contains("tests/inputs/missing.txt: "));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files_from() -> TestResult {
+    run(
+        &["--files-from=tests/inputs/files.txt"],
+        "tests/expected/files.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files_from_and_files() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from=tests/inputs/files0.txt", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_files_from() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("cannot open '{}' for reading: .* [(]os error 2[)]", bad);
+    Command::cargo_bin(PRG)?
+        .args(["--files-from", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn format_json() -> TestResult {
+    run(
+        &["--format=json", FOX, "tests/inputs/two words.txt"],
+        "tests/expected/json.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn format_json_errors() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([
+            "-lmL",
+            "--format=json",
+            "--files0-from=tests/inputs/files0_bad.txt",
+        ])
+        .assert()
+        .success()
+        .stdout(fs::read_to_string(
+            "tests/expected/files0_bad.txt.json.out",
+        )?)
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn format_csv() -> TestResult {
+    run(
+        &["--format", "csv", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.csv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn format_tsv() -> TestResult {
+    run(
+        &[
+            "-lwL",
+            "--format=tsv",
+            "--files-from=tests/inputs/files.txt",
+        ],
+        "tests/expected/files.txt.tsv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format=xml", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'xml' isn't a valid value"));
+    Ok(())
+}